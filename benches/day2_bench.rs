@@ -3,7 +3,7 @@ use std::hint::black_box;
 
 use aoc2024::day2;
 
-const INPUT_REAL: &'static str = include_str!("../input/2024/day2.txt");
+const INPUT_REAL: &str = include_str!("../input/2024/day2.txt");
 
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("day2_part1", |b| {
@@ -3,15 +3,47 @@ use std::hint::black_box;
 
 use aoc2024::day3;
 
-const INPUT_REAL: &'static str = include_str!("../input/2024/day3.txt");
+const INPUT_REAL: &str = include_str!("../input/2024/day3.txt");
 
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("day3_part1", |b| {
         b.iter(|| day3::part1(black_box(INPUT_REAL)))
     });
+    c.bench_function("day3_part1_simd", |b| {
+        b.iter(|| day3::part1_simd(black_box(INPUT_REAL)))
+    });
+    c.bench_function("day3_part1_safe", |b| {
+        b.iter(|| day3::part1_safe(black_box(INPUT_REAL)))
+    });
     c.bench_function("day3_part2", |b| {
         b.iter(|| day3::part2(black_box(INPUT_REAL)))
     });
+    c.bench_function("day3_part2_simd", |b| {
+        b.iter(|| day3::part2_simd(black_box(INPUT_REAL)))
+    });
+    c.bench_function("day3_part2_safe", |b| {
+        b.iter(|| day3::part2_safe(black_box(INPUT_REAL)))
+    });
+    c.bench_function("day3_part2_parallel", |b| {
+        b.iter(|| day3::part2_parallel(black_box(INPUT_REAL)))
+    });
+
+    let input_large = INPUT_REAL.repeat(256);
+    c.bench_function("day3_part2_simd_large", |b| {
+        b.iter(|| day3::part2_simd(black_box(&input_large)))
+    });
+    c.bench_function("day3_part2_parallel_large", |b| {
+        b.iter(|| day3::part2_parallel(black_box(&input_large)))
+    });
+
+    // Chunks without any instruction must not scan ahead
+    let input_sparse = "x".repeat(4 << 20) + "mul(2,3)";
+    c.bench_function("day3_part2_simd_sparse", |b| {
+        b.iter(|| day3::part2_simd(black_box(&input_sparse)))
+    });
+    c.bench_function("day3_part2_parallel_sparse", |b| {
+        b.iter(|| day3::part2_parallel(black_box(&input_sparse)))
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -0,0 +1,22 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+
+use aoc2024::day6;
+
+// Random lab, not a puzzle input
+const INPUT_GENERATED: &str = include_str!("../input/generated/day6.txt");
+
+fn criterion_benchmark(c: &mut Criterion) {
+    c.bench_function("day6_part1", |b| {
+        b.iter(|| day6::part1(black_box(INPUT_GENERATED)))
+    });
+    c.bench_function("day6_part2", |b| {
+        b.iter(|| day6::part2(black_box(INPUT_GENERATED)))
+    });
+    c.bench_function("day6_part2_safe", |b| {
+        b.iter(|| day6::part2_safe(black_box(INPUT_GENERATED)))
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,24 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+
+use aoc2024::day7;
+
+const INPUT_REAL: &str = include_str!("../input/2024/day7.txt");
+
+fn criterion_benchmark(c: &mut Criterion) {
+    c.bench_function("day7_part1", |b| {
+        b.iter(|| day7::part1(black_box(INPUT_REAL)))
+    });
+    c.bench_function("day7_part1_brute", |b| {
+        b.iter(|| day7::part1_brute(black_box(INPUT_REAL)))
+    });
+    c.bench_function("day7_part2", |b| {
+        b.iter(|| day7::part2(black_box(INPUT_REAL)))
+    });
+    c.bench_function("day7_part2_brute", |b| {
+        b.iter(|| day7::part2_brute(black_box(INPUT_REAL)))
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
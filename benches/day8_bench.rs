@@ -0,0 +1,19 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+
+use aoc2024::day8;
+
+// Random map, not a puzzle input
+const INPUT_GENERATED: &str = include_str!("../input/generated/day8.txt");
+
+fn criterion_benchmark(c: &mut Criterion) {
+    c.bench_function("day8_part1", |b| {
+        b.iter(|| day8::part1(black_box(INPUT_GENERATED)))
+    });
+    c.bench_function("day8_part2", |b| {
+        b.iter(|| day8::part2(black_box(INPUT_GENERATED)))
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,22 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+
+use aoc2024::day9;
+
+// Random disk map, not a puzzle input
+const INPUT_GENERATED: &str = include_str!("../input/generated/day9.txt");
+
+fn criterion_benchmark(c: &mut Criterion) {
+    c.bench_function("day9_part1", |b| {
+        b.iter(|| day9::part1(black_box(INPUT_GENERATED)))
+    });
+    c.bench_function("day9_part2", |b| {
+        b.iter(|| day9::part2(black_box(INPUT_GENERATED)))
+    });
+    c.bench_function("day9_part2_brute", |b| {
+        b.iter(|| day9::part2_brute(black_box(INPUT_GENERATED)))
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
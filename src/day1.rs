@@ -1,12 +1,13 @@
-use crate::parsers::{num_from_digits, u64_from_ascii};
-use anyhow::{anyhow, Result};
+use crate::parsers::BytesAsciiExt;
+use anyhow::{anyhow, bail, Result};
 use aoc_runner_derive::aoc;
 use std::cmp::Ordering;
+use std::collections::HashMap;
 
 #[aoc(day1, part1, AoCS)]
 pub fn part1(input: &str) -> u64 {
     let input = input.as_bytes();
-    let (mut left, mut right) = parse_optimistic(input);
+    let (mut left, mut right) = parse_optimistic(input).unwrap();
     left.sort_unstable();
     right.sort_unstable();
     std::iter::zip(left, right)
@@ -17,28 +18,158 @@ pub fn part1(input: &str) -> u64 {
 #[aoc(day1, part2, AoCS)]
 pub fn part2(input: &str) -> u64 {
     let input = input.as_bytes();
-    let parsed = parse_optimistic(input);
+    let parsed = parse_optimistic(input).unwrap();
     solve_p2(parsed)
 }
 
-pub fn parse_optimistic(input: &[u8]) -> (Vec<u64>, Vec<u64>) {
-    const WIDTH: usize = 14;
-    const HEIGHT: usize = 1000;
-    let mut left = Vec::with_capacity(HEIGHT);
-    let mut right = Vec::with_capacity(HEIGHT);
+#[aoc(day1, part1, radix)]
+pub fn part1_radix(input: &str) -> u64 {
+    let input = input.as_bytes();
+    let (mut left, mut right) = parse_optimistic(input).unwrap();
+    radix_sort(&mut left);
+    radix_sort(&mut right);
+    std::iter::zip(left, right)
+        .map(|(l, r)| l.abs_diff(r))
+        .sum()
+}
 
-    for i in 0..HEIGHT {
-        let [l1, l2, l3, l4, l5, b' ', b' ', b' ', r1, r2, r3, r4, r5] =
-            input[i * WIDTH..i * WIDTH + WIDTH - 1]
-        else {
-            unreachable!()
-        };
-        let l = num_from_digits!(u64, l1, l2, l3, l4, l5);
-        let r = num_from_digits!(u64, r1, r2, r3, r4, r5);
+#[aoc(day1, part2, histogram)]
+pub fn part2_histogram(input: &str) -> u64 {
+    let input = input.as_bytes();
+    let parsed = parse_optimistic(input).unwrap();
+    solve_p2_histogram(parsed)
+}
+
+const RADIX_BITS: u32 = 11;
+
+/// LSD radix sort, only does as many passes as the largest value needs
+pub fn radix_sort(values: &mut Vec<u64>) {
+    const MASK: u64 = (1 << RADIX_BITS) - 1;
+
+    let max = values.iter().copied().max().unwrap_or(0);
+    let passes = (u64::BITS - max.leading_zeros()).div_ceil(RADIX_BITS);
+    let mut buf = vec![0; values.len()];
+    for pass in 0..passes {
+        let shift = pass * RADIX_BITS;
+        let mut offsets = [0usize; 1 << RADIX_BITS];
+        for &v in values.iter() {
+            offsets[((v >> shift) & MASK) as usize] += 1;
+        }
+        let mut sum = 0;
+        for offset in offsets.iter_mut() {
+            let count = *offset;
+            *offset = sum;
+            sum += count;
+        }
+        for &v in values.iter() {
+            let offset = &mut offsets[((v >> shift) & MASK) as usize];
+            buf[*offset] = v;
+            *offset += 1;
+        }
+        std::mem::swap(values, &mut buf);
+    }
+}
+
+/// Location IDs in the puzzle are 5-digit numbers
+pub const MAX_LOCATION_ID: u64 = 99_999;
+
+/// Similarity score from a table of occurrences in the right list, no sorting required.
+///
+/// The table is small as long as IDs don't exceed [`MAX_LOCATION_ID`], lists with larger IDs
+/// fall back to [`solve_p2`].
+pub fn solve_p2_histogram((left, right): (Vec<u64>, Vec<u64>)) -> u64 {
+    let max = right.iter().copied().max().unwrap_or(0);
+    if max > MAX_LOCATION_ID {
+        return solve_p2((left, right));
+    }
+
+    let mut counts = vec![0u32; max as usize + 1];
+    for r in right {
+        counts[r as usize] += 1;
+    }
+    left.into_iter()
+        .map(|l| counts.get(l as usize).map_or(0, |&c| l * c as u64))
+        .sum()
+}
+
+/// Parse lists with the fixed width fast path, falling back to [`parse`] if lines are irregular
+pub fn parse_optimistic(input: &[u8]) -> Result<(Vec<u64>, Vec<u64>)> {
+    match parse_fixed_width(input) {
+        Some(lists) => Ok(lists),
+        None => parse_bytes(input),
+    }
+}
+
+/// Parse lists assuming every line has the same shape as the first one.
+///
+/// Returns `None` if any line deviates from it.
+pub fn parse_fixed_width(input: &[u8]) -> Option<(Vec<u64>, Vec<u64>)> {
+    let l_width = input.iter().position(|&c| c == b' ')?;
+    let sep = input[l_width..].iter().position(|&c| c != b' ')?;
+    let r_width = input[l_width + sep..]
+        .iter()
+        .position(|&c| c == b'\n')
+        .unwrap_or(input.len() - l_width - sep);
+    if l_width == 0 || l_width > 19 || r_width == 0 || r_width > 19 {
+        return None;
+    }
+
+    let width = l_width + sep + r_width + 1;
+    let height = input.len().div_ceil(width);
+    // Only the last line is allowed to miss its newline
+    if input.len() + 1 < height * width {
+        return None;
+    }
+
+    // Known widths are passed as constants, so that digit loops are unrolled after inlining
+    match (l_width, sep, r_width) {
+        (5, 3, 5) => parse_columns(input, 5, 3, 5, height),
+        (1, 3, 1) => parse_columns(input, 1, 3, 1, height),
+        _ => parse_columns(input, l_width, sep, r_width, height),
+    }
+}
+
+#[inline(always)]
+fn parse_columns(
+    input: &[u8],
+    l_width: usize,
+    sep: usize,
+    r_width: usize,
+    height: usize,
+) -> Option<(Vec<u64>, Vec<u64>)> {
+    let width = l_width + sep + r_width + 1;
+    let mut left = Vec::with_capacity(height);
+    let mut right = Vec::with_capacity(height);
+
+    // Validity is accumulated instead of checked per line to keep the loop branch-free
+    let mut valid = true;
+    for line in input.chunks(width) {
+        let (l, line) = line.split_at(l_width);
+        let (spaces, line) = line.split_at(sep);
+        let (r, newline) = line.split_at(r_width);
+
+        let (l, l_valid) = fixed_width_digits(l);
+        let (r, r_valid) = fixed_width_digits(r);
+        valid &= l_valid & r_valid;
+        valid &= spaces.iter().fold(true, |acc, &c| acc & (c == b' '));
+        valid &= newline.iter().fold(true, |acc, &c| acc & (c == b'\n'));
         left.push(l);
         right.push(r);
     }
-    (left, right)
+
+    valid.then_some((left, right))
+}
+
+#[inline(always)]
+fn fixed_width_digits(digits: &[u8]) -> (u64, bool) {
+    digits.iter().fold((0, true), |(acc, valid), &d| {
+        let d = d.wrapping_sub(b'0');
+        // The value is thrown away when invalid, it may as well wrap
+        (
+            acc.wrapping_mul(10).wrapping_add(d as u64),
+            valid & (d < 10),
+        )
+    })
 }
 
 #[aoc(day1, part1, default)]
@@ -54,8 +185,8 @@ pub fn part1_safe(input: &str) -> Result<u64> {
 
 #[aoc(day1, part2, default)]
 pub fn part2_safe(input: &str) -> Result<u64> {
-    let parsed = parse(input)?;
-    Ok(solve_p2(parsed))
+    let (left, right) = parse(input)?;
+    Ok(solve_p2((left, right)))
 }
 
 pub fn solve_p2((mut left, mut right): (Vec<u64>, Vec<u64>)) -> u64 {
@@ -95,30 +226,235 @@ pub fn solve_p2((mut left, mut right): (Vec<u64>, Vec<u64>)) -> u64 {
 }
 
 pub fn parse(input: &str) -> Result<(Vec<u64>, Vec<u64>)> {
-    let bytes = input.as_bytes();
+    parse_bytes(input.as_bytes())
+}
+
+fn parse_bytes(bytes: &[u8]) -> Result<(Vec<u64>, Vec<u64>)> {
     let mut left = Vec::with_capacity(1000);
     let mut right = Vec::with_capacity(1000);
-    for line in bytes.split_inclusive(|&c| c == b'\n') {
-        let line = line.trim_ascii_start();
-        let space = line
-            .iter()
-            .position(|&c| c == b' ')
-            .ok_or_else(|| anyhow!("not enough location ids"))?;
-        let (l, r) = line.split_at(space);
-        let l = u64_from_ascii(l);
-        let r = u64_from_ascii(r);
-        left.push(l);
-        right.push(r);
+    for (idx, line) in bytes.ascii_lines().enumerate() {
+        let line_nr = idx + 1;
+        let mut ids = line.ascii_words().filter(|id| !id.is_empty());
+        let (l, r) = match (ids.next(), ids.next(), ids.next()) {
+            (Some(l), Some(r), None) => (l, r),
+            (None, _, _) => bail!("line {line_nr}: blank line"),
+            (Some(_), None, _) => bail!("line {line_nr}: missing right location id"),
+            (Some(_), Some(_), Some(_)) => bail!("line {line_nr}: too many location ids"),
+        };
+        left.push(parse_id(l).map_err(|e| anyhow!("line {line_nr}: {e}"))?);
+        right.push(parse_id(r).map_err(|e| anyhow!("line {line_nr}: {e}"))?);
     }
+    // Every line has exactly two ids, so both lists always have the same length
+    debug_assert_eq!(left.len(), right.len());
     Ok((left, right))
 }
 
+fn parse_id(id: &[u8]) -> Result<u64> {
+    if !id.iter().all(u8::is_ascii_digit) {
+        bail!("invalid location id {:?}", String::from_utf8_lossy(id));
+    }
+    id.iter()
+        .try_fold(0u64, |acc, &d| {
+            acc.checked_mul(10)?.checked_add((d - b'0') as u64)
+        })
+        .ok_or_else(|| anyhow!("location id {:?} is too large", String::from_utf8_lossy(id)))
+}
+
+/// List a location ID belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// Pair of location lists that can be updated one ID at a time.
+///
+/// Total distance and similarity score are kept up to date on every update instead of being
+/// recomputed from scratch. IDs are limited to the `0..=max_id` range given on construction.
+///
+/// Pairing sorted lists and summing up distances is the same as summing `|L(x) - R(x)|` over
+/// all `x`, where `L(x)` and `R(x)` are the numbers of IDs `<= x` in each list. Inserting an ID
+/// shifts one of the counts by one for every `x` past the ID, which is tracked in blocks with a
+/// lazy offset, so an update touches one block element-wise and the rest in constant time.
+#[derive(Debug, Clone)]
+pub struct LocationLists {
+    counts: [Vec<u32>; 2],
+    lens: [usize; 2],
+    similarity_score: u64,
+    /// `L(x) - R(x)` without the lazy offset of the block `x` belongs to
+    diff: Vec<i32>,
+    blocks: Vec<DiffBlock>,
+    /// Sum of `|L(x) - R(x)|` over all `x`
+    diff_sum: u64,
+}
+
+const DIFF_BLOCK_SIZE: usize = 512;
+
+#[derive(Debug, Clone)]
+struct DiffBlock {
+    len: usize,
+    offset: i32,
+    /// Number of elements with non-negative difference, offset included
+    non_negative: usize,
+    /// Number of elements by difference, offset excluded
+    histogram: HashMap<i32, usize>,
+}
+
+impl DiffBlock {
+    fn count(&self, diff: i32) -> usize {
+        self.histogram
+            .get(&(diff - self.offset))
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+impl LocationLists {
+    pub fn new(max_id: u64) -> Self {
+        let size = max_id as usize + 1;
+        let blocks = (0..size.div_ceil(DIFF_BLOCK_SIZE))
+            .map(|b| {
+                let len = DIFF_BLOCK_SIZE.min(size - b * DIFF_BLOCK_SIZE);
+                DiffBlock {
+                    len,
+                    offset: 0,
+                    non_negative: len,
+                    histogram: HashMap::from([(0, len)]),
+                }
+            })
+            .collect();
+        LocationLists {
+            counts: [vec![0; size], vec![0; size]],
+            lens: [0; 2],
+            similarity_score: 0,
+            diff: vec![0; size],
+            blocks,
+            diff_sum: 0,
+        }
+    }
+
+    /// Build from parsed lists, e.g. the output of [`parse`]
+    pub fn from_lists((left, right): (Vec<u64>, Vec<u64>), max_id: u64) -> Result<Self> {
+        let mut lists = LocationLists::new(max_id);
+        for id in left {
+            lists.insert(Side::Left, id)?;
+        }
+        for id in right {
+            lists.insert(Side::Right, id)?;
+        }
+        Ok(lists)
+    }
+
+    pub fn insert(&mut self, side: Side, id: u64) -> Result<()> {
+        let Some(idx) = self.index(id) else {
+            bail!("location id {id} is out of range");
+        };
+        let other = self.counts[Self::other(side)][idx] as u64;
+        self.similarity_score += id * other;
+        self.counts[side as usize][idx] += 1;
+        self.lens[side as usize] += 1;
+        self.shift_suffix(idx, Self::diff_sign(side));
+        Ok(())
+    }
+
+    /// Remove one occurrence of `id`, returns `false` if the list doesn't contain it
+    pub fn remove(&mut self, side: Side, id: u64) -> bool {
+        let Some(idx) = self.index(id) else {
+            return false;
+        };
+        if self.counts[side as usize][idx] == 0 {
+            return false;
+        }
+        let other = self.counts[Self::other(side)][idx] as u64;
+        self.similarity_score -= id * other;
+        self.counts[side as usize][idx] -= 1;
+        self.lens[side as usize] -= 1;
+        self.shift_suffix(idx, -Self::diff_sign(side));
+        true
+    }
+
+    pub fn len(&self, side: Side) -> usize {
+        self.lens[side as usize]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lens == [0; 2]
+    }
+
+    /// Part 1 answer, only defined if both lists have the same length
+    pub fn total_distance(&self) -> Option<u64> {
+        (self.lens[0] == self.lens[1]).then_some(self.diff_sum)
+    }
+
+    /// Part 2 answer
+    pub fn similarity_score(&self) -> u64 {
+        self.similarity_score
+    }
+
+    fn index(&self, id: u64) -> Option<usize> {
+        usize::try_from(id)
+            .ok()
+            .filter(|&idx| idx < self.diff.len())
+    }
+
+    fn other(side: Side) -> usize {
+        match side {
+            Side::Left => Side::Right as usize,
+            Side::Right => Side::Left as usize,
+        }
+    }
+
+    fn diff_sign(side: Side) -> i32 {
+        match side {
+            Side::Left => 1,
+            Side::Right => -1,
+        }
+    }
+
+    /// Add `delta` of `1` or `-1` to the difference at `start` and every position after it
+    fn shift_suffix(&mut self, start: usize, delta: i32) {
+        let first_block = start / DIFF_BLOCK_SIZE;
+        let first_block_end = ((first_block + 1) * DIFF_BLOCK_SIZE).min(self.diff.len());
+        let block = &mut self.blocks[first_block];
+        for raw in &mut self.diff[start..first_block_end] {
+            let old = *raw + block.offset;
+            let new = old + delta;
+            self.diff_sum = self.diff_sum + new.unsigned_abs() as u64 - old.unsigned_abs() as u64;
+            if (old >= 0) != (new >= 0) {
+                if new >= 0 {
+                    block.non_negative += 1;
+                } else {
+                    block.non_negative -= 1;
+                }
+            }
+            *block.histogram.entry(*raw).or_default() -= 1;
+            *raw += delta;
+            *block.histogram.entry(*raw).or_default() += 1;
+        }
+
+        for block in &mut self.blocks[first_block + 1..] {
+            if delta > 0 {
+                // Non-negative differences grow, negative ones shrink
+                let growing = block.non_negative;
+                self.diff_sum = self.diff_sum + growing as u64 - (block.len - growing) as u64;
+                block.non_negative += block.count(-1);
+            } else {
+                // Positive differences shrink, the rest grow
+                let shrinking = block.non_negative - block.count(0);
+                self.diff_sum = self.diff_sum + (block.len - shrinking) as u64 - shrinking as u64;
+                block.non_negative -= block.count(0);
+            }
+            block.offset += delta;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const DAY1_INPUT: &'static str = include_str!("../input/2024/day1.txt");
-    const DAY1_EXAMPLE: &'static str = "\
+    const DAY1_INPUT: &str = include_str!("../input/2024/day1.txt");
+    const DAY1_EXAMPLE: &str = "\
 3   4
 4   3
 2   5
@@ -129,23 +465,189 @@ mod tests {
 
     #[test]
     fn part1_example() {
+        assert_eq!(part1(DAY1_EXAMPLE), 11);
+        assert_eq!(part1_radix(DAY1_EXAMPLE), 11);
         assert_eq!(part1_safe(DAY1_EXAMPLE).unwrap(), 11);
     }
 
     #[test]
     fn part1_input() {
         assert_eq!(part1(DAY1_INPUT), 1941353);
+        assert_eq!(part1_radix(DAY1_INPUT), 1941353);
         assert_eq!(part1_safe(DAY1_INPUT).unwrap(), 1941353);
     }
 
     #[test]
     fn part2_example() {
+        assert_eq!(part2(DAY1_EXAMPLE), 31);
+        assert_eq!(part2_histogram(DAY1_EXAMPLE), 31);
         assert_eq!(part2_safe(DAY1_EXAMPLE).unwrap(), 31);
     }
 
     #[test]
     fn part2_input() {
         assert_eq!(part2(DAY1_INPUT), 22539317);
+        assert_eq!(part2_histogram(DAY1_INPUT), 22539317);
         assert_eq!(part2_safe(DAY1_INPUT).unwrap(), 22539317);
     }
+
+    #[test]
+    fn fixed_width_shapes() {
+        let lists = (vec![12, 3], vec![456, 7]);
+        assert_eq!(parse_fixed_width(b"12 456\n03 007\n"), Some(lists.clone()));
+        assert_eq!(parse_fixed_width(b"12 456\n03 007"), Some(lists.clone()));
+        assert_eq!(parse_fixed_width(b"12 456\n03 007\n\n"), None);
+        assert_eq!(parse_fixed_width(b"12 456\n3  007\n"), None);
+        assert_eq!(parse_fixed_width(b"12 456\n03 07\n"), None);
+        assert_eq!(parse_fixed_width(b"12 456\n03  07\n"), None);
+        assert_eq!(parse_fixed_width(b"12 45a\n03 007\n"), None);
+        assert_eq!(parse_optimistic(b"12 456\n3 7\n").unwrap(), lists);
+        assert_eq!(parse_fixed_width(b"aaaaaaaaaaaaaaaaaaa   1\n"), None);
+        assert!(parse_optimistic(b"aaaaaaaaaaaaaaaaaaa   1\n").is_err());
+
+        let input = DAY1_INPUT.as_bytes();
+        assert_eq!(
+            parse_fixed_width(input).unwrap(),
+            parse(DAY1_INPUT).unwrap()
+        );
+        assert_eq!(
+            parse_fixed_width(input.trim_ascii_end()).unwrap(),
+            parse(DAY1_INPUT).unwrap()
+        );
+    }
+
+    #[test]
+    fn radix_sort_wide() {
+        let mut values = vec![u64::MAX, 0, 1 << 40, 3, 1 << 40, 99_999, 2048, 2047, 7];
+        let mut expected = values.clone();
+        expected.sort_unstable();
+        radix_sort(&mut values);
+        assert_eq!(values, expected);
+
+        let mut values = vec![];
+        radix_sort(&mut values);
+        assert!(values.is_empty());
+    }
+
+    #[test]
+    fn histogram_wide_ids() {
+        let lists = (vec![3, 100_000, 100_000, 5], vec![100_000, 3, 3, 4]);
+        assert_eq!(solve_p2_histogram(lists.clone()), solve_p2(lists));
+    }
+
+    #[test]
+    fn invalid_lines() {
+        let error = |input: &str| part1_safe(input).unwrap_err().to_string();
+        assert_eq!(
+            error("3   4\n4\n2   5\n"),
+            "line 2: missing right location id"
+        );
+        assert_eq!(error("3   4\n\n2   5\n"), "line 2: blank line");
+        assert_eq!(error("3   4\n4   3\n   \n"), "line 3: blank line");
+        assert_eq!(error("3   4 5\n"), "line 1: too many location ids");
+        assert_eq!(
+            error("3   4\n4   x3\n"),
+            "line 2: invalid location id \"x3\""
+        );
+        assert_eq!(
+            error("1   99999999999999999999\n"),
+            "line 1: location id \"99999999999999999999\" is too large"
+        );
+
+        assert_eq!(part1_safe("  3   4\r\n4 3").unwrap(), 0);
+    }
+
+    #[test]
+    fn location_lists_example() {
+        let mut lists =
+            LocationLists::from_lists(parse(DAY1_EXAMPLE).unwrap(), MAX_LOCATION_ID).unwrap();
+        assert_eq!(lists.total_distance(), Some(11));
+        assert_eq!(lists.similarity_score(), 31);
+
+        lists.insert(Side::Left, 9).unwrap();
+        assert_eq!(lists.total_distance(), None);
+        assert_eq!(lists.similarity_score(), 40);
+        lists.insert(Side::Right, 1).unwrap();
+        assert_eq!(
+            lists.total_distance(),
+            Some(part1_safe("3 4\n4 3\n2 5\n1 3\n3 9\n3 3\n9 1\n").unwrap())
+        );
+
+        assert!(!lists.remove(Side::Right, 2));
+        assert!(lists.remove(Side::Right, 3));
+        assert!(lists.remove(Side::Left, 9));
+        assert_eq!(lists.similarity_score(), 3 * 3 * 2 + 4 + 1);
+        assert_eq!(lists.len(Side::Left), 6);
+        assert_eq!(lists.len(Side::Right), 6);
+
+        assert!(lists.insert(Side::Left, MAX_LOCATION_ID + 1).is_err());
+        assert!(!lists.remove(Side::Left, u64::MAX));
+    }
+
+    #[test]
+    fn location_lists_input() {
+        let lists = LocationLists::from_lists(parse(DAY1_INPUT).unwrap(), MAX_LOCATION_ID).unwrap();
+        assert_eq!(lists.total_distance(), Some(1941353));
+        assert_eq!(lists.similarity_score(), 22539317);
+    }
+
+    #[test]
+    fn location_lists_updates() {
+        // Small ID range and a fixed LCG to get plenty of collisions without a dependency on rand
+        const MAX_ID: u64 = 2000;
+        let mut seed = 0x2024_u64;
+        let mut next = |bound: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
+        };
+
+        let mut lists = LocationLists::new(MAX_ID);
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+        for step in 0..3000 {
+            let side = if next(2) == 0 {
+                Side::Left
+            } else {
+                Side::Right
+            };
+            let list = match side {
+                Side::Left => &mut left,
+                Side::Right => &mut right,
+            };
+            if next(4) == 0 && !list.is_empty() {
+                let id = list.swap_remove(next(list.len() as u64) as usize);
+                assert!(lists.remove(side, id));
+            } else {
+                let id = next(MAX_ID + 1);
+                list.push(id);
+                lists.insert(side, id).unwrap();
+            }
+
+            assert_eq!(
+                lists.similarity_score(),
+                solve_p2((left.clone(), right.clone()))
+            );
+            if step % 50 == 0 && left.len() == right.len() {
+                let input = std::iter::zip(&left, &right)
+                    .map(|(l, r)| format!("{l}   {r}\n"))
+                    .collect::<String>();
+                assert_eq!(lists.total_distance(), Some(part1_safe(&input).unwrap()));
+                assert_eq!(lists.similarity_score(), part2_safe(&input).unwrap());
+            }
+        }
+
+        // Balance lists to check the distance at the end
+        while left.len() > right.len() {
+            lists.remove(Side::Left, left.pop().unwrap());
+        }
+        while right.len() > left.len() {
+            lists.remove(Side::Right, right.pop().unwrap());
+        }
+        let input = std::iter::zip(&left, &right)
+            .map(|(l, r)| format!("{l}   {r}\n"))
+            .collect::<String>();
+        assert_eq!(lists.total_distance(), Some(part1_safe(&input).unwrap()));
+    }
 }
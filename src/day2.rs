@@ -1,141 +1,505 @@
 use crate::parsers::BytesAsciiExt;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use aoc_runner_derive::aoc;
+use std::fmt::{Display, Write};
+use std::io::BufRead;
+use std::ops::RangeInclusive;
 
 #[aoc(day2, part1, AoCS)]
 pub fn part1(input: &str) -> usize {
-    let bytes = input.as_bytes();
-    let result = bytes.ascii_lines().filter(verify_report).count();
-    result
+    count_safe_unchecked::<1>(input)
 }
 
+/// Smallest difference between adjacent levels of a safe report
+const MIN_STEP: i32 = 1;
+/// Largest difference between adjacent levels of a safe report
+const MAX_STEP: i32 = 3;
+
 fn parse_input_digit(num: &[u8]) -> i32 {
-    (match *num {
-        [tens, ones] => (tens - b'0') * 10 + ones - b'0',
-        [ones] => ones - b'0',
-        _ => unsafe { std::hint::unreachable_unchecked() },
-    }) as i32
-}
-
-#[derive(Debug, PartialEq, Eq)]
-enum ReportStatus {
-    Uninitialized,
-    Unknown(i32),
-    Descending(i32),
-    Ascending(i32),
-}
-
-fn verify_report(&report: &&[u8]) -> bool {
-    report
-        .ascii_words()
-        .map(parse_input_digit)
-        .try_fold(ReportStatus::Uninitialized, analyze_next_lvl)
-        .is_ok()
-}
-
-fn analyze_next_lvl(acc: ReportStatus, lvl: i32) -> Result<ReportStatus, ()> {
-    match acc {
-        ReportStatus::Uninitialized => Ok(ReportStatus::Unknown(lvl)),
-        ReportStatus::Unknown(prev) => match prev - lvl {
-            -3..=-1 => Ok(ReportStatus::Ascending(lvl)),
-            1..=3 => Ok(ReportStatus::Descending(lvl)),
-            _ => Err(()),
-        },
-        ReportStatus::Descending(prev) => {
-            if let 1..=3 = prev - lvl {
-                Ok(ReportStatus::Descending(lvl))
-            } else {
-                Err(())
+    match *num {
+        [tens, ones] => ((tens - b'0') * 10 + ones - b'0') as i32,
+        [ones] => (ones - b'0') as i32,
+        // Puzzle input doesn't have wider levels, keep them correct but out of the fast path
+        _ => num.iter().fold(0i32, |acc, &d| {
+            acc.wrapping_mul(10)
+                .wrapping_add(d.wrapping_sub(b'0') as i32)
+        }),
+    }
+}
+
+#[aoc(day2, part2, AoCS)]
+pub fn part2(input: &str) -> usize {
+    count_safe_unchecked::<2>(input)
+}
+
+/// Same as [`count_safe`] with `WINDOW - 1` levels removed at most and the puzzle's steps,
+/// without validating levels or allocating for each report
+fn count_safe_unchecked<const WINDOW: usize>(input: &str) -> usize {
+    let mut validator = FixedValidator::<WINDOW>::new();
+    let bytes = input.as_bytes();
+    bytes
+        .ascii_lines()
+        .filter(|report| !report.is_empty())
+        .filter(|report| {
+            validator.reset();
+            let mut levels = report
+                .ascii_words()
+                .filter(|lvl| !lvl.is_empty())
+                .map(parse_input_digit);
+            let finished = levels.all(|lvl| {
+                validator.push(lvl);
+                !validator.is_hopeless()
+            });
+            finished && validator.is_safe()
+        })
+        .count()
+}
+
+/// Same as [`ReportValidator`] for the puzzle's steps, with `WINDOW - 1` levels removed at most
+/// so that recent levels fit in an array and every loop has a constant bound
+#[derive(Debug, Clone, Copy)]
+struct FixedValidator<const WINDOW: usize> {
+    len: usize,
+    /// Most recent levels, newest first, with the fewest levels removed before each of them in
+    /// an ascending and in a descending report keeping it. Counts above `MAX_REMOVED` are all
+    /// as bad, so they are capped to `WINDOW`.
+    recent: [(i32, [usize; 2]); WINDOW],
+}
+
+impl<const WINDOW: usize> FixedValidator<WINDOW> {
+    const MAX_REMOVED: usize = WINDOW - 1;
+
+    /// Stands for levels before the first one, too far from any level to be kept before it
+    const UNUSED: (i32, [usize; 2]) = (i32::MIN / 2, [WINDOW; 2]);
+
+    fn new() -> Self {
+        FixedValidator {
+            len: 0,
+            recent: [Self::UNUSED; WINDOW],
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    #[inline(always)]
+    fn push(&mut self, lvl: i32) {
+        // Keeping no level before this one at worst
+        let mut removed = [self.len.min(WINDOW); 2];
+        for (gap, &(prev, prev_removed)) in self.recent.iter().enumerate() {
+            if let MIN_STEP..=MAX_STEP = lvl.wrapping_sub(prev) {
+                removed[0] = removed[0].min(prev_removed[0] + gap);
+            }
+            if let MIN_STEP..=MAX_STEP = prev.wrapping_sub(lvl) {
+                removed[1] = removed[1].min(prev_removed[1] + gap);
             }
         }
-        ReportStatus::Ascending(prev) => {
-            if let 1..=3 = lvl - prev {
-                Ok(ReportStatus::Ascending(lvl))
-            } else {
-                Err(())
+        self.recent.copy_within(..WINDOW - 1, 1);
+        self.recent[0] = (lvl, removed);
+        self.len += 1;
+    }
+
+    /// Same as [`ReportValidator::is_hopeless`]
+    #[inline(always)]
+    fn is_hopeless(&self) -> bool {
+        self.len > Self::MAX_REMOVED
+            && self
+                .recent
+                .iter()
+                .all(|(_, removed)| removed[0].min(removed[1]) > Self::MAX_REMOVED)
+    }
+
+    fn is_safe(&self) -> bool {
+        // Levels after the last kept one are removed as well
+        self.len == 0
+            || self.recent.iter().enumerate().any(|(after, (_, removed))| {
+                removed[0].min(removed[1]) + after <= Self::MAX_REMOVED
+            })
+    }
+}
+
+/// Streaming check of a single report, fed one level at a time.
+///
+/// Runs the same dynamic programming as [`SafetyRules::removals`], only remembering the last
+/// `max_removed + 1` levels since no level kept in a safe report can follow an older one.
+#[derive(Debug, Clone)]
+pub struct ReportValidator {
+    rules: SafetyRules,
+    /// Number of levels pushed since the last reset
+    len: usize,
+    /// Up to `max_removed + 1` most recent levels, used as a ring buffer
+    recent: Vec<RecentLevel>,
+    /// Where the oldest of `recent` is once it is full
+    oldest: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RecentLevel {
+    index: usize,
+    lvl: i32,
+    /// Fewest levels removed before this one in an ascending and in a descending report
+    /// keeping it
+    removed: [usize; 2],
+}
+
+impl ReportValidator {
+    /// Validator for part 1 rules
+    pub fn new() -> Self {
+        ReportValidator::with_rules(SafetyRules::PART1)
+    }
+
+    /// Validator for part 2 rules, tolerating a single bad level
+    pub fn dampened() -> Self {
+        ReportValidator::with_rules(SafetyRules::PART2)
+    }
+
+    pub fn with_rules(rules: SafetyRules) -> Self {
+        ReportValidator {
+            recent: Vec::with_capacity(rules.max_removed + 1),
+            rules,
+            len: 0,
+            oldest: 0,
+        }
+    }
+
+    /// Forget all levels to start validating the next report
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.recent.clear();
+        self.oldest = 0;
+    }
+
+    #[inline]
+    pub fn push(&mut self, lvl: i32) {
+        let (min, max) = (*self.rules.steps.start(), *self.rules.steps.end());
+        let i = self.len;
+        // Keeping no level before this one at worst
+        let mut removed = [i; 2];
+        for prev in &self.recent {
+            let gap = i - prev.index - 1;
+            let up = lvl - prev.lvl;
+            if (min..=max).contains(&up) {
+                removed[0] = removed[0].min(prev.removed[0] + gap);
+            }
+            if (min..=max).contains(&-up) {
+                removed[1] = removed[1].min(prev.removed[1] + gap);
             }
         }
+        let level = RecentLevel {
+            index: i,
+            lvl,
+            removed,
+        };
+        if self.recent.len() <= self.rules.max_removed {
+            self.recent.push(level);
+        } else {
+            self.recent[self.oldest] = level;
+            self.oldest = if self.oldest == self.rules.max_removed {
+                0
+            } else {
+                self.oldest + 1
+            };
+        }
+        self.len += 1;
+    }
+
+    /// Whether the report is unsafe whatever levels come next, every recent level already needs
+    /// too many removed before it
+    pub fn is_hopeless(&self) -> bool {
+        let max_removed = self.rules.max_removed;
+        self.len > max_removed
+            && self
+                .recent
+                .iter()
+                .all(|level| level.removed.iter().all(|&removed| removed > max_removed))
+    }
+
+    /// Whether no level was pushed since the last reset
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether levels pushed since the last reset form a safe report
+    pub fn is_safe(&self) -> bool {
+        // Levels after the last kept one are removed as well
+        self.is_empty()
+            || self.recent.iter().any(|level| {
+                let after = self.len - 1 - level.index;
+                level
+                    .removed
+                    .iter()
+                    .any(|&removed| removed + after <= self.rules.max_removed)
+            })
+    }
+}
+
+impl Default for ReportValidator {
+    fn default() -> Self {
+        ReportValidator::new()
+    }
+}
+
+impl Extend<i32> for ReportValidator {
+    fn extend<T: IntoIterator<Item = i32>>(&mut self, iter: T) {
+        for lvl in iter {
+            self.push(lvl);
+        }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-enum ReportStatusDampened {
-    Descending(bool, i32, i32),
-    Ascending(bool, i32, i32),
+/// Number of safe reports read line by line from `reader`, without loading the whole input
+pub fn count_safe_streaming(mut reader: impl BufRead, rules: &SafetyRules) -> Result<usize> {
+    let mut validator = ReportValidator::with_rules(rules.clone());
+    let mut line = String::new();
+    let mut count = 0;
+    let mut line_nr = 0;
+    while reader.read_line(&mut line)? != 0 {
+        line_nr += 1;
+        if line.trim_ascii().is_empty() {
+            line.clear();
+            continue;
+        }
+        validator.reset();
+        for lvl in line.split_ascii_whitespace() {
+            let lvl = lvl
+                .parse()
+                .map_err(|e| anyhow!("line {line_nr}: invalid level {lvl:?}: {e}"))?;
+            validator.push(lvl);
+        }
+        count += validator.is_safe() as usize;
+        line.clear();
+    }
+    Ok(count)
 }
 
-fn analyze_next_lvl_dampened(
-    acc: ReportStatusDampened,
-    curr: i32,
-) -> Result<ReportStatusDampened, ()> {
-    match acc {
-        ReportStatusDampened::Descending(corrected, postpred, pred) => {
-            if let 1..=3 = pred - curr {
-                Ok(ReportStatusDampened::Descending(corrected, pred, curr))
-            } else if !corrected {
-                if matches!(postpred - curr, 1..=3) {
-                    Ok(ReportStatusDampened::Descending(true, pred, curr))
-                } else {
-                    Ok(ReportStatusDampened::Descending(true, postpred, pred))
+#[aoc(day2, part1, default)]
+pub fn part1_safe(input: &str) -> Result<usize> {
+    count_safe(input, &SafetyRules::PART1)
+}
+
+#[aoc(day2, part2, default)]
+pub fn part2_safe(input: &str) -> Result<usize> {
+    count_safe(input, &SafetyRules::PART2)
+}
+
+/// Number of reports in `input` that are safe according to `rules`, blank lines aren't reports
+pub fn count_safe(input: &str, rules: &SafetyRules) -> Result<usize> {
+    let mut levels = Vec::new();
+    let mut count = 0;
+    for line in input.lines() {
+        levels.clear();
+        for lvl in line.split_ascii_whitespace() {
+            levels.push(lvl.parse()?);
+        }
+        if levels.is_empty() {
+            continue;
+        }
+        count += rules.is_safe(&levels) as usize;
+    }
+    Ok(count)
+}
+
+/// What makes a report safe
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SafetyRules {
+    /// How many levels the problem dampener may remove
+    pub max_removed: usize,
+    /// Allowed difference between adjacent levels, in the direction of the report
+    pub steps: RangeInclusive<i32>,
+}
+
+impl SafetyRules {
+    pub const PART1: SafetyRules = SafetyRules {
+        max_removed: 0,
+        steps: MIN_STEP..=MAX_STEP,
+    };
+
+    pub const PART2: SafetyRules = SafetyRules {
+        max_removed: 1,
+        steps: MIN_STEP..=MAX_STEP,
+    };
+
+    pub fn is_safe(&self, levels: &[i32]) -> bool {
+        self.removals(levels).is_some()
+    }
+
+    /// Fewest level indices to remove to make the report safe, `None` if more than
+    /// `max_removed` levels would have to go
+    pub fn removals(&self, levels: &[i32]) -> Option<Vec<usize>> {
+        let ascending = self.removals_directed(levels, |prev, lvl| lvl - prev);
+        let descending = self.removals_directed(levels, |prev, lvl| prev - lvl);
+        match (ascending, descending) {
+            (Some(asc), Some(desc)) if desc.len() < asc.len() => Some(desc),
+            (asc, desc) => asc.or(desc),
+        }
+    }
+
+    /// Dynamic programming over kept levels.
+    ///
+    /// `removed[i]` is the fewest levels removed before level `i` in a valid report that keeps
+    /// level `i`, and `kept[i]` is the previous level kept in that report. It is only worth
+    /// looking at most `max_removed + 1` levels back, any further gap removes too many levels
+    /// anyway. Ties prefer removing earlier levels, the same way puzzle text explains reports.
+    fn removals_directed(
+        &self,
+        levels: &[i32],
+        step: impl Fn(i32, i32) -> i32,
+    ) -> Option<Vec<usize>> {
+        let max_removed = self.max_removed;
+        let Some(last) = levels.len().checked_sub(1) else {
+            return Some(Vec::new());
+        };
+
+        let mut removed = Vec::with_capacity(levels.len());
+        let mut kept = Vec::with_capacity(levels.len());
+        for (i, &lvl) in levels.iter().enumerate() {
+            let mut best = (i, None);
+            for p in i.saturating_sub(max_removed + 1)..i {
+                let count = removed[p] + i - p - 1;
+                if count <= best.0 && self.steps.contains(&step(levels[p], lvl)) {
+                    best = (count, Some(p));
                 }
-            } else {
-                Err(())
             }
+            removed.push(best.0);
+            kept.push(best.1);
+        }
+
+        // Levels after the last kept one are removed as well
+        let end =
+            (last.saturating_sub(max_removed)..=last).min_by_key(|&i| removed[i] + last - i)?;
+        if removed[end] + last - end > max_removed {
+            return None;
         }
-        ReportStatusDampened::Ascending(corrected, postpred, pred) => {
-            if let 1..=3 = curr - pred {
-                Ok(ReportStatusDampened::Ascending(corrected, pred, curr))
-            } else if !corrected {
-                if matches!(curr - postpred, 1..=3) {
-                    Ok(ReportStatusDampened::Ascending(true, pred, curr))
-                } else {
-                    Ok(ReportStatusDampened::Ascending(true, postpred, pred))
+        let mut keep = vec![false; levels.len()];
+        let mut next = Some(end);
+        while let Some(i) = next {
+            keep[i] = true;
+            next = kept[i];
+        }
+        Some((0..levels.len()).filter(|&i| !keep[i]).collect())
+    }
+
+    /// First pair of adjacent levels breaking the rules, ignoring the dampener
+    pub fn first_violation(&self, levels: &[i32]) -> Option<Violation> {
+        let mut direction = 0;
+        for (i, pair) in levels.windows(2).enumerate() {
+            let step = pair[1] - pair[0];
+            let kind = if step != 0 && direction != 0 && step.signum() != direction {
+                ViolationKind::DirectionChange
+            } else if self.steps.contains(&step.abs()) {
+                if step != 0 {
+                    direction = step.signum();
                 }
+                continue;
+            } else if step == 0 {
+                ViolationKind::Flat
+            } else if step.abs() > *self.steps.end() {
+                ViolationKind::StepTooLarge
             } else {
-                Err(())
-            }
+                ViolationKind::StepTooSmall
+            };
+            return Some(Violation {
+                index: i + 1,
+                prev: pair[0],
+                lvl: pair[1],
+                kind,
+            });
+        }
+        None
+    }
+
+    pub fn diagnose(&self, levels: &[i32]) -> Diagnosis {
+        match self.removals(levels) {
+            Some(removed) if removed.is_empty() => Diagnosis::Safe,
+            Some(removed) => Diagnosis::SafeWithout(removed),
+            None => Diagnosis::Unsafe(self.first_violation(levels).unwrap()),
         }
     }
 }
 
-fn report_trend_is_ascending(x: [i32; 4]) -> bool {
-    (x[0] < x[1]) as u32 + (x[1] < x[2]) as u32 + (x[2] < x[3]) as u32 > 1
+/// Outcome of checking a single report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnosis {
+    Safe,
+    /// Safe once levels at these indices are removed
+    SafeWithout(Vec<usize>),
+    /// Unsafe even with the dampener, with the first rule broken by the full report
+    Unsafe(Violation),
 }
 
-fn verify_report_dampened(&report: &&[u8]) -> bool {
-    let mut report = report.ascii_words().map(parse_input_digit);
-    let head = unsafe {
-        [
-            report.next().unwrap_unchecked(),
-            report.next().unwrap_unchecked(),
-            report.next().unwrap_unchecked(),
-            report.next().unwrap_unchecked(),
-        ]
-    };
-    let status = if report_trend_is_ascending(head) {
-        if !matches!(head[1] - head[0], 1..=3) && matches!(head[2] - head[1], 1..=3) {
-            ReportStatusDampened::Ascending(true, head[1] - 1, head[1] - 1)
+/// Adjacent levels breaking the rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Violation {
+    /// Index of the second level of the pair
+    pub index: usize,
+    pub prev: i32,
+    pub lvl: i32,
+    pub kind: ViolationKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// Report goes up after going down or vice versa
+    DirectionChange,
+    StepTooLarge,
+    StepTooSmall,
+    /// Levels are equal
+    Flat,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Violation {
+            prev, lvl, kind, ..
+        } = *self;
+        let change = if lvl > prev {
+            "an increase"
         } else {
-            ReportStatusDampened::Ascending(false, head[0], head[0])
+            "a decrease"
+        };
+        let diff = lvl.abs_diff(prev);
+        match kind {
+            ViolationKind::DirectionChange => {
+                write!(f, "{prev} {lvl} is {change}, the report changes direction")
+            }
+            ViolationKind::StepTooLarge => {
+                write!(f, "{prev} {lvl} is {change} of {diff}, too large")
+            }
+            ViolationKind::StepTooSmall => {
+                write!(f, "{prev} {lvl} is {change} of {diff}, too small")
+            }
+            ViolationKind::Flat => write!(f, "{prev} {lvl} is neither an increase or a decrease"),
         }
-    } else if !matches!(head[0] - head[1], 1..=3) && matches!(head[1] - head[2], 1..=3) {
-        ReportStatusDampened::Descending(true, head[1] + 1, head[1] + 1)
-    } else {
-        ReportStatusDampened::Descending(false, head[0], head[0])
-    };
-    head[1..]
-        .iter()
-        .copied()
-        .chain(report)
-        .try_fold(status, analyze_next_lvl_dampened)
-        .is_ok()
+    }
 }
 
-#[aoc(day2, part2, AoCS)]
-pub fn part2(input: &str) -> usize {
-    let bytes = input.as_bytes();
-    let result = bytes.ascii_lines().filter(verify_report_dampened).count();
-    result
+/// Diagnosis of every report, one line each
+pub fn explain(input: &str, rules: &SafetyRules) -> Result<String> {
+    let mut res = String::new();
+    for line in input.lines() {
+        let levels = line
+            .split_ascii_whitespace()
+            .map(|lvl| lvl.parse())
+            .collect::<Result<Vec<i32>, _>>()?;
+        if levels.is_empty() {
+            continue;
+        }
+        write!(res, "{line}: ")?;
+        match rules.diagnose(&levels) {
+            Diagnosis::Safe => writeln!(res, "safe")?,
+            Diagnosis::SafeWithout(removed) => {
+                let plural = if removed.len() > 1 { "s" } else { "" };
+                let removed = removed
+                    .iter()
+                    .map(|&i| format!("{i} ({})", levels[i]))
+                    .collect::<Vec<_>>();
+                writeln!(res, "safe without level{plural} {}", removed.join(", "))?
+            }
+            Diagnosis::Unsafe(violation) => writeln!(res, "unsafe, {violation}")?,
+        }
+    }
+    Ok(res)
 }
 
 pub fn parse(input: &str) -> Vec<Vec<i32>> {
@@ -150,8 +514,8 @@ pub fn parse(input: &str) -> Vec<Vec<i32>> {
 mod tests {
     use super::*;
 
-    const DAY2_INPUT: &'static str = include_str!("../input/2024/day2.txt");
-    const DAY2_EXAMPLE: &'static str = "\
+    const DAY2_INPUT: &str = include_str!("../input/2024/day2.txt");
+    const DAY2_EXAMPLE: &str = "\
 7 6 4 2 1
 1 2 7 8 9
 9 7 6 2 1
@@ -163,15 +527,353 @@ mod tests {
     #[test]
     fn part1_example() {
         assert_eq!(part1(DAY2_EXAMPLE), 2);
+        assert_eq!(part1_safe(DAY2_EXAMPLE).unwrap(), 2);
     }
 
     #[test]
     fn part1_input() {
         assert_eq!(part1(DAY2_INPUT), 299);
+        assert_eq!(part1_safe(DAY2_INPUT).unwrap(), 299);
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_eq!(part2(DAY2_EXAMPLE), 4);
+        assert_eq!(part2_safe(DAY2_EXAMPLE).unwrap(), 4);
     }
 
     #[test]
     fn part2_input() {
         assert_eq!(part2(DAY2_INPUT), 364);
+        assert_eq!(part2_safe(DAY2_INPUT).unwrap(), 364);
+    }
+
+    #[test]
+    fn short_reports() {
+        let cases = [
+            ("5", 1, 1),
+            ("5 6", 1, 1),
+            ("5 9", 0, 1),
+            ("5 5", 0, 1),
+            ("1 5 9", 0, 0),
+            ("1 5 6", 0, 1),
+            ("3 2 2", 0, 1),
+            ("3 2 1", 1, 1),
+            ("1 1 1", 0, 0),
+            ("1  2", 1, 1),
+            (" 5 4  3", 1, 1),
+            ("5 4  9", 0, 1),
+        ];
+        for (report, p1, p2) in cases {
+            assert_eq!(part1(report), p1, "{report}");
+            assert_eq!(part1_safe(report).unwrap(), p1, "{report}");
+            assert_eq!(part2(report), p2, "{report}");
+            assert_eq!(part2_safe(report).unwrap(), p2, "{report}");
+        }
+    }
+
+    #[test]
+    fn wide_levels() {
+        let cases = [
+            ("100 101 104", 1, 1),
+            ("100 99 200 98", 0, 1),
+            ("1000 1003 1006 1010", 0, 1),
+            ("5 105 6 7 8", 0, 1),
+        ];
+        for (report, p1, p2) in cases {
+            assert_eq!(part1(report), p1, "{report}");
+            assert_eq!(part1_safe(report).unwrap(), p1, "{report}");
+            assert_eq!(part2(report), p2, "{report}");
+            assert_eq!(part2_safe(report).unwrap(), p2, "{report}");
+        }
+    }
+
+    #[test]
+    fn blank_lines() {
+        let input = "7 6 4 2 1\n\n   \n1 2 7 8 9\n\n";
+        assert_eq!(part1(input), 1);
+        assert_eq!(part1_safe(input).unwrap(), 1);
+        assert_eq!(part2(input), 1);
+        assert_eq!(part2_safe(input).unwrap(), 1);
+        assert_eq!(
+            count_safe_streaming(input.as_bytes(), &SafetyRules::PART2).unwrap(),
+            1
+        );
+        assert_eq!(
+            explain(input, &SafetyRules::PART2).unwrap(),
+            "7 6 4 2 1: safe\n1 2 7 8 9: unsafe, 2 7 is an increase of 5, too large\n"
+        );
+    }
+
+    #[test]
+    fn random_reports() {
+        let mut state = 0x2024_u64;
+        let mut next = |n: u64| {
+            // xorshift64
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % n
+        };
+        let mut input = String::new();
+        for _ in 0..2000 {
+            let len = 1 + next(8);
+            let mut lvl = 50 + next(80) as i64;
+            let ascending = next(2) == 0;
+            for i in 0..len {
+                // Mostly valid steps, with a few bad levels thrown in
+                let step = match next(10) {
+                    0 => next(10) as i64 - 5,
+                    1 => 4 + next(3) as i64,
+                    _ => 1 + next(3) as i64,
+                };
+                lvl += if ascending { step } else { -step };
+                if i > 0 {
+                    input.push(' ');
+                }
+                input += &lvl.to_string();
+            }
+            input.push('\n');
+        }
+        assert_eq!(part1(&input), part1_safe(&input).unwrap());
+        assert_eq!(part2(&input), part2_safe(&input).unwrap());
+    }
+
+    #[test]
+    fn streaming_input() {
+        assert_eq!(
+            count_safe_streaming(DAY2_EXAMPLE.as_bytes(), &SafetyRules::PART1).unwrap(),
+            2
+        );
+        assert_eq!(
+            count_safe_streaming(DAY2_EXAMPLE.as_bytes(), &SafetyRules::PART2).unwrap(),
+            4
+        );
+        assert_eq!(
+            count_safe_streaming(DAY2_INPUT.as_bytes(), &SafetyRules::PART1).unwrap(),
+            299
+        );
+        assert_eq!(
+            count_safe_streaming(DAY2_INPUT.as_bytes(), &SafetyRules::PART2).unwrap(),
+            364
+        );
+        let err = count_safe_streaming("1 2 3\n\n1 2 x\n".as_bytes(), &SafetyRules::PART1);
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "line 3: invalid level \"x\": invalid digit found in string"
+        );
+    }
+
+    /// Every report of `len` levels between 0 and `max`
+    fn all_reports(len: u32, max: i32) -> impl Iterator<Item = Vec<i32>> {
+        let base = max as usize + 1;
+        (0..base.pow(len)).map(move |mut n| {
+            (0..len)
+                .map(|_| {
+                    let lvl = (n % base) as i32;
+                    n /= base;
+                    lvl
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn validator_matches_rules() {
+        // Safe once a single level is removed, not always one the trend of the head points to
+        let tricky = "\
+0 1 4 2 6
+1 4 2 6 7
+9 8 5 7 3
+1 2 3 9 4
+5 1 2 3 4
+1 2 3 4 0
+3 1 2 3 4
+";
+        assert_eq!(
+            count_safe_streaming(tricky.as_bytes(), &SafetyRules::PART2).unwrap(),
+            7
+        );
+        assert_eq!(
+            count_safe_streaming(tricky.as_bytes(), &SafetyRules::PART2).unwrap(),
+            part2_safe(tricky).unwrap()
+        );
+
+        let mut fixed = (
+            FixedValidator::<1>::new(),
+            FixedValidator::<2>::new(),
+            FixedValidator::<3>::new(),
+        );
+        for max_removed in 0..3 {
+            let rules = SafetyRules {
+                max_removed,
+                ..SafetyRules::PART1
+            };
+            let mut validator = ReportValidator::with_rules(rules.clone());
+            for len in 0..7 {
+                for report in all_reports(len, 5) {
+                    validator.reset();
+                    validator.extend(report.iter().copied());
+                    let safe = rules.is_safe(&report);
+                    assert_eq!(validator.is_safe(), safe, "{report:?} {rules:?}");
+
+                    fixed.0.reset();
+                    fixed.1.reset();
+                    fixed.2.reset();
+                    for &lvl in &report {
+                        fixed.0.push(lvl);
+                        fixed.1.push(lvl);
+                        fixed.2.push(lvl);
+                    }
+                    let fixed_safe = [fixed.0.is_safe(), fixed.1.is_safe(), fixed.2.is_safe()];
+                    assert_eq!(fixed_safe[max_removed], safe, "{report:?} {rules:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn validator_levels() {
+        let mut validator = ReportValidator::dampened();
+        for (report, safe) in [
+            (&[][..], true),
+            (&[5], true),
+            (&[5, 9], true),
+            (&[1, 5, 9], false),
+            (&[1, 3, 2, 4, 5], true),
+            (&[1, 2, 7, 8, 9], false),
+            (&[8, 6, 4, 4, 1], true),
+        ] {
+            validator.reset();
+            validator.extend(report.iter().copied());
+            assert_eq!(validator.is_safe(), safe, "{report:?}");
+        }
+
+        let mut validator = ReportValidator::new();
+        validator.push(1);
+        validator.push(3);
+        assert!(validator.is_safe());
+        validator.push(2);
+        assert!(!validator.is_safe());
+        validator.push(4);
+        assert!(!validator.is_safe());
+        validator.reset();
+        validator.push(4);
+        assert!(validator.is_safe());
+    }
+
+    #[test]
+    fn tolerate_more_levels() {
+        let rules = |max_removed| SafetyRules {
+            max_removed,
+            ..SafetyRules::PART1
+        };
+        // 1 and 9 break the report
+        let report = [1, 5, 6, 9, 7, 8];
+        assert!(!rules(0).is_safe(&report));
+        assert!(!rules(1).is_safe(&report));
+        assert!(rules(2).is_safe(&report));
+        // Removing the first and last levels
+        let report = [9, 1, 2, 3, 4, 0];
+        assert!(!rules(1).is_safe(&report));
+        assert!(rules(2).is_safe(&report));
+        // Any two levels are fine once all but one are removed
+        assert!(rules(3).is_safe(&[1, 1, 1, 1]));
+        assert!(!rules(2).is_safe(&[1, 1, 1, 1]));
+
+        assert_eq!(count_safe(DAY2_EXAMPLE, &rules(2)).unwrap(), 6);
+        assert_eq!(
+            explain("1 5 6 9 7 8", &rules(2)).unwrap(),
+            "1 5 6 9 7 8: safe without levels 0 (1), 3 (9)\n"
+        );
+    }
+
+    #[test]
+    fn example_diagnosis() {
+        let explained = explain(DAY2_EXAMPLE, &SafetyRules::PART2).unwrap();
+        assert_eq!(
+            explained,
+            "\
+7 6 4 2 1: safe
+1 2 7 8 9: unsafe, 2 7 is an increase of 5, too large
+9 7 6 2 1: unsafe, 6 2 is a decrease of 4, too large
+1 3 2 4 5: safe without level 1 (3)
+8 6 4 4 1: safe without level 2 (4)
+1 3 6 7 9: safe
+"
+        );
+
+        let rules = SafetyRules::PART1;
+        assert_eq!(
+            rules.diagnose(&[1, 3, 2, 4, 5]),
+            Diagnosis::Unsafe(Violation {
+                index: 2,
+                prev: 3,
+                lvl: 2,
+                kind: ViolationKind::DirectionChange
+            })
+        );
+        assert_eq!(
+            rules
+                .first_violation(&[8, 6, 4, 4, 1])
+                .map(|v| (v.index, v.kind)),
+            Some((3, ViolationKind::Flat))
+        );
+        let rules = SafetyRules {
+            max_removed: 0,
+            steps: 2..=3,
+        };
+        assert_eq!(
+            rules.first_violation(&[1, 3, 4]).map(|v| (v.index, v.kind)),
+            Some((2, ViolationKind::StepTooSmall))
+        );
+    }
+
+    #[test]
+    fn diagnosis_matches_count() {
+        for rules in [SafetyRules::PART1, SafetyRules::PART2] {
+            let safe = DAY2_INPUT
+                .lines()
+                .filter(|line| {
+                    let levels = line
+                        .split_ascii_whitespace()
+                        .map(|lvl| lvl.parse().unwrap())
+                        .collect::<Vec<i32>>();
+                    match rules.diagnose(&levels) {
+                        Diagnosis::Safe => true,
+                        Diagnosis::SafeWithout(removed) => {
+                            assert!(removed.len() <= rules.max_removed);
+                            let levels = (0..levels.len())
+                                .filter(|i| !removed.contains(i))
+                                .map(|i| levels[i])
+                                .collect::<Vec<_>>();
+                            assert_eq!(rules.first_violation(&levels), None);
+                            true
+                        }
+                        Diagnosis::Unsafe(_) => false,
+                    }
+                })
+                .count();
+            assert_eq!(safe, count_safe(DAY2_INPUT, &rules).unwrap());
+        }
+    }
+
+    #[test]
+    fn custom_steps() {
+        let rules = SafetyRules {
+            max_removed: 0,
+            steps: 2..=2,
+        };
+        assert!(rules.is_safe(&[1, 3, 5, 7]));
+        assert!(rules.is_safe(&[7, 5, 3]));
+        assert!(!rules.is_safe(&[1, 2, 4]));
+        let rules = SafetyRules {
+            max_removed: 1,
+            ..rules
+        };
+        assert!(rules.is_safe(&[1, 2, 3]));
+        assert!(!rules.is_safe(&[1, 2, 4, 5]));
+        let input = "1 2 3\n1 2 4 5\n";
+        assert_eq!(count_safe_streaming(input.as_bytes(), &rules).unwrap(), 1);
     }
 }
@@ -1,198 +1,428 @@
 use crate::parsers::{num_from_digits, u64_from_ascii};
 use anyhow::Result;
 use aoc_runner_derive::aoc;
+use rayon::prelude::*;
+use std::ops::Range;
 
 #[aoc(day3, part1, AoCS)]
 pub fn part1(input: &str) -> u64 {
-    let mut input = input.as_bytes();
+    sum_products(Tokenizer::optimistic(input).muls_only())
+}
+
+#[aoc(day3, part2, AoCS)]
+pub fn part2(input: &str) -> u64 {
+    sum_enabled_products(Tokenizer::optimistic(input))
+}
+
+#[aoc(day3, part1, simd)]
+pub fn part1_simd(input: &str) -> u64 {
+    sum_products(Tokenizer::vectorized(input).muls_only())
+}
+
+#[aoc(day3, part2, simd)]
+pub fn part2_simd(input: &str) -> u64 {
+    sum_enabled_products(Tokenizer::vectorized(input))
+}
+
+#[aoc(day3, part2, parallel)]
+pub fn part2_parallel(input: &str) -> u64 {
+    sum_enabled_products_chunked(input, CHUNK_LEN)
+}
+
+#[aoc(day3, part1, default)]
+pub fn part1_safe(input: &str) -> Result<u64> {
+    Ok(sum_products(Tokenizer::new(input).muls_only()))
+}
+
+#[aoc(day3, part2, default)]
+pub fn part2_safe(input: &str) -> Result<u64> {
+    Ok(sum_enabled_products(Tokenizer::new(input)))
+}
+
+pub fn sum_products(instructions: impl Iterator<Item = Instruction>) -> u64 {
+    instructions
+        .map(|instruction| match instruction.kind {
+            InstructionKind::Mul(l, r) => l * r,
+            _ => 0,
+        })
+        .sum()
+}
+
+pub fn sum_enabled_products(mut instructions: Tokenizer) -> u64 {
     let mut res = 0;
-    loop {
-        let Some(pos) = input.iter().position(|&c| c == b'm') else {
-            break;
-        };
-        let (offset, v) = parse_ascii_mul(&input[pos..]);
-        if let Some(v) = v {
-            res += v;
+    while let Some(instruction) = instructions.next() {
+        match instruction.kind {
+            InstructionKind::Mul(l, r) => res += l * r,
+            // Nothing counts until the next `do()`, no need to parse anything in between
+            InstructionKind::Dont => instructions.skip_past("do()"),
+            InstructionKind::Do => {}
         }
-        input = &input[offset + pos..];
     }
     res
 }
 
-fn parse_ascii_mul(expr: &[u8]) -> (usize, Option<u64>) {
-    if expr.starts_with(b"mul(") {
-        parse_mul_args(expr)
-    } else {
-        (1, None)
-    }
+/// Input bytes evaluated by each task of [`part2_parallel`]
+pub const CHUNK_LEN: usize = 1 << 14;
+
+/// Same as [`sum_enabled_products`], but each `chunk_len` bytes of input are evaluated in parallel
+pub fn sum_enabled_products_chunked(input: &str, chunk_len: usize) -> u64 {
+    let chunk_len = chunk_len.max(1);
+    (0..input.len().div_ceil(chunk_len))
+        .into_par_iter()
+        .map(|i| {
+            let start = i * chunk_len;
+            Segment::evaluate(input, start..input.len().min(start + chunk_len))
+        })
+        .reduce(Segment::default, Segment::then)
+        .products[Segment::ENABLED]
 }
 
-/// Parse mul function arguments with explicit pattern matching for every possible case
-#[inline(always)]
-fn parse_mul_args(args: &[u8]) -> (usize, Option<u64>) {
-    match args[4..] {
-        [l1, b',', r1, b')', ..] => (
-            8,
-            Some(num_from_digits!(u64, l1) * num_from_digits!(u64, r1)),
-        ),
-        [l1, b',', r1, r2, b')', ..] => (
-            9,
-            Some(num_from_digits!(u64, l1) * num_from_digits!(u64, r1, r2)),
-        ),
-        [l1, b',', r1, r2, r3, b')', ..] => (
-            10,
-            Some(num_from_digits!(u64, l1) * num_from_digits!(u64, r1, r2, r3)),
-        ),
-        [l1, l2, b',', r1, b')', ..] => (
-            9,
-            Some(num_from_digits!(u64, l1, l2) * num_from_digits!(u64, r1)),
-        ),
-        [l1, l2, b',', r1, r2, b')', ..] => (
-            10,
-            Some(num_from_digits!(u64, l1, l2) * num_from_digits!(u64, r1, r2)),
-        ),
-        [l1, l2, b',', r1, r2, r3, b')', ..] => (
-            11,
-            Some(num_from_digits!(u64, l1, l2) * num_from_digits!(u64, r1, r2, r3)),
-        ),
-        [l1, l2, l3, b',', r1, b')', ..] => (
-            10,
-            Some(num_from_digits!(u64, l1, l2, l3) * num_from_digits!(u64, r1)),
-        ),
-        [l1, l2, l3, b',', r1, r2, b')', ..] => (
-            11,
-            Some(num_from_digits!(u64, l1, l2, l3) * num_from_digits!(u64, r1, r2)),
-        ),
-        [l1, l2, l3, b',', r1, r2, r3, b')', ..] => (
-            12,
-            Some(num_from_digits!(u64, l1, l2, l3) * num_from_digits!(u64, r1, r2, r3)),
-        ),
-        _ => (4, None),
-    }
+/// Effect of the instructions starting in a range of input, for both states it can be entered in
+///
+/// Chaining segments is associative, so they can be evaluated independently and combined in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    /// Sum of enabled products, indexed by starting state
+    products: [u64; 2],
+    /// Whether `mul` is enabled at the end, indexed by starting state
+    enabled: [bool; 2],
 }
 
-#[aoc(day3, part2, AoCS)]
-pub fn part2(input: &str) -> u64 {
-    let mut input = input;
-    let mut res = 0;
-    let mut enabled = true;
-    loop {
-        if enabled {
-            let Some(pos) = input.bytes().position(|c| c == b'm' || c == b'd') else {
-                break;
-            };
-            let (offset, enable, v) = parse_enabled(&input.as_bytes()[pos..]);
-            enabled = enable;
-            if let Some(v) = v {
-                res += v;
+impl Segment {
+    const DISABLED: usize = 0;
+    const ENABLED: usize = 1;
+
+    fn evaluate(input: &str, range: Range<usize>) -> Self {
+        // Instructions starting in range may end past it, but nothing further has to be scanned
+        let mut end = input.len().min(range.end + MAX_INSTRUCTION_LEN - 1);
+        while !input.is_char_boundary(end) {
+            end += 1;
+        }
+        let mut instructions = Tokenizer::vectorized(&input[..end]);
+        // Instructions can't contain the start of another one, so starting in the middle of one
+        // doesn't find anything the sequential tokenizer would have skipped
+        instructions.skip_to(range.start);
+
+        let mut segment = Segment::default();
+        for instruction in instructions.take_while(|i| i.span.start < range.end) {
+            match instruction.kind {
+                InstructionKind::Mul(l, r) => {
+                    for state in [Self::DISABLED, Self::ENABLED] {
+                        if segment.enabled[state] {
+                            segment.products[state] += l * r;
+                        }
+                    }
+                }
+                InstructionKind::Do => segment.enabled = [true; 2],
+                InstructionKind::Dont => segment.enabled = [false; 2],
             }
-            input = &input[offset + pos..];
-        } else {
-            let Some(pos) = input.find("do()") else {
-                break;
-            };
-            enabled = true;
-            input = &input[pos + 4..];
         }
+        segment
     }
-    res
-}
 
-fn parse_enabled(expr: &[u8]) -> (usize, bool, Option<u64>) {
-    if expr.starts_with(b"mul(") {
-        let (offset, v) = parse_mul_args(expr);
-        (offset, true, v)
-    } else if expr.starts_with(b"don't()") {
-        (7, false, None)
-    } else {
-        (1, true, None)
+    /// Segment running `self` then `next`
+    fn then(self, next: Self) -> Self {
+        let through = |state: usize| self.enabled[state] as usize;
+        Segment {
+            products: [Self::DISABLED, Self::ENABLED]
+                .map(|state| self.products[state] + next.products[through(state)]),
+            enabled: [Self::DISABLED, Self::ENABLED].map(|state| next.enabled[through(state)]),
+        }
     }
 }
 
-#[aoc(day3, part1, default)]
-pub fn part1_safe(input: &str) -> Result<u64> {
-    let mut input = input;
-    let mut res = 0;
-    while input.len() >= 8 {
-        if let Some(v) = parse_mul(input) {
-            res += v;
+impl Default for Segment {
+    /// Empty segment, leaves the state unchanged
+    fn default() -> Self {
+        Segment {
+            products: [0; 2],
+            enabled: [false, true],
         }
-        input = &input[1..]
     }
-    Ok(res)
 }
 
-pub fn parse_mul(expr: &str) -> Option<u64> {
-    if expr.starts_with("mul(") {
-        let args = &expr[4..12];
-        let sep = args.find(',')?;
-        let l = &args[..sep];
-        let cp = args[sep + 1..].find(')')?;
-        let r = &args[sep + 1..sep + 1 + cp];
-        assert!(l.bytes().all(|b| b.is_ascii_digit()));
-        assert!(r.bytes().all(|b| b.is_ascii_digit()));
+/// Instruction found in corrupted memory, `span` is its byte range in the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub kind: InstructionKind,
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstructionKind {
+    /// `mul(l,r)`
+    Mul(u64, u64),
+    /// `do()`
+    Do,
+    /// `don't()`
+    Dont,
+}
+
+/// Whether `c` is the first byte of any instruction recognized by [`Tokenizer::parse_at`]
+#[inline(always)]
+fn is_instruction_start(c: u8) -> bool {
+    matches!(c, b'm' | b'd')
+}
+
+/// Length of the longest instruction recognized by [`Tokenizer::parse_at`], `mul(123,456)`
+const MAX_INSTRUCTION_LEN: usize = 12;
+
+/// Prefixes shared by every instruction recognized by [`Tokenizer::parse_at`]
+const INSTRUCTION_PREFIXES: &[&[u8]] = &[b"mul(", b"do"];
+
+/// Prefix of `mul` instructions, the only ones a tokenizer without conditionals looks for
+const MUL_PREFIX: &[&[u8]] = &[b"mul("];
+
+/// Stream of valid instructions in corrupted memory, everything in between is skipped
+#[derive(Debug, Clone)]
+pub struct Tokenizer<'a> {
+    input: &'a str,
+    pos: usize,
+    strict: bool,
+    vectorized: bool,
+    /// Whether `do()` and `don't()` are looked for as well as `mul`
+    conditionals: bool,
+}
+
+impl<'a> Tokenizer<'a> {
+    /// Tokenizer validating every instruction argument
+    pub fn new(input: &'a str) -> Self {
+        Tokenizer {
+            input,
+            pos: 0,
+            strict: true,
+            vectorized: false,
+            conditionals: true,
+        }
+    }
+
+    /// Tokenizer trusting that `mul` arguments are made of digits, which holds for puzzle inputs
+    pub fn optimistic(input: &'a str) -> Self {
+        Tokenizer {
+            input,
+            pos: 0,
+            strict: false,
+            vectorized: false,
+            conditionals: true,
+        }
+    }
+
+    /// Optimistic tokenizer looking for instruction candidates 16 bytes at a time
+    pub fn vectorized(input: &'a str) -> Self {
+        Tokenizer {
+            vectorized: true,
+            ..Tokenizer::optimistic(input)
+        }
+    }
+
+    /// Only look for `mul` instructions, skipping `do()` and `don't()` like any other corruption
+    pub fn muls_only(self) -> Self {
+        Tokenizer {
+            conditionals: false,
+            ..self
+        }
+    }
+
+    /// Continue at byte `pos`, whatever comes before is skipped
+    pub fn skip_to(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+
+    /// Continue after the next occurrence of `needle`, or at the end of input if there is none
+    pub fn skip_past(&mut self, needle: &str) {
+        let offset = if self.vectorized {
+            scan::find_prefixes(&self.input.as_bytes()[self.pos..], &[needle.as_bytes()])
+        } else {
+            self.input[self.pos..].find(needle)
+        };
+        self.pos = match offset {
+            Some(offset) => self.pos + offset + needle.len(),
+            None => self.input.len(),
+        };
+    }
 
-        Some(u64_from_ascii(l.as_bytes()) * u64_from_ascii(r.as_bytes()))
-    } else {
-        None
+    /// Parse instruction starting at `start`, returns its length and kind
+    #[inline(always)]
+    fn parse_at(&self, start: usize) -> Option<(usize, InstructionKind)> {
+        // Candidates start with an ASCII byte, slicing bytes saves checking for a char boundary
+        let expr = &self.input.as_bytes()[start..];
+        if expr.starts_with(b"mul(") {
+            let (len, args) = if self.strict {
+                parse_mul(&self.input[start..])?
+            } else {
+                parse_mul_args(expr)?
+            };
+            Some((len, InstructionKind::Mul(args.0, args.1)))
+        } else if !self.conditionals {
+            None
+        } else if expr.starts_with(b"do()") {
+            Some((4, InstructionKind::Do))
+        } else if expr.starts_with(b"don't()") {
+            Some((7, InstructionKind::Dont))
+        } else {
+            None
+        }
     }
 }
 
-#[aoc(day3, part2, default)]
-pub fn part2_safe(input: &str) -> Result<u64> {
-    let mut input = input;
-    let mut res = 0;
-    let mut enabled = true;
-    while input.len() >= 8 {
-        match parse_fn(input) {
-            Some(Expr::Stop) => {
-                enabled = false;
+impl Iterator for Tokenizer<'_> {
+    type Item = Instruction;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Instruction> {
+        let bytes = self.input.as_bytes();
+        loop {
+            let rest = &bytes[self.pos..];
+            let offset = match (self.vectorized, self.conditionals) {
+                (true, true) => scan::find_prefixes(rest, INSTRUCTION_PREFIXES),
+                (true, false) => scan::find_prefixes(rest, MUL_PREFIX),
+                (false, true) => rest.iter().position(|&c| is_instruction_start(c)),
+                (false, false) => rest.iter().position(|&c| c == b'm'),
+            }?;
+            let start = self.pos + offset;
+            if let Some((len, kind)) = self.parse_at(start) {
+                self.pos = start + len;
+                return Some(Instruction {
+                    kind,
+                    span: start..start + len,
+                });
             }
-            Some(Expr::Start) => {
-                enabled = true;
+            self.pos = start + 1;
+        }
+    }
+}
+
+mod scan {
+    /// Number of candidate positions checked at once
+    const LANES: usize = 16;
+
+    /// Offset of the first occurrence of any of `patterns` in `bytes`
+    #[inline(always)]
+    pub fn find_prefixes(bytes: &[u8], patterns: &[&[u8]]) -> Option<usize> {
+        let longest = patterns.iter().map(|p| p.len()).max()?;
+        let mut start = 0;
+        // Every pattern must be readable in full from the last lane of the block
+        #[cfg(target_arch = "x86_64")]
+        while start + LANES - 1 + longest <= bytes.len() {
+            let mask = sse2::prefix_mask(&bytes[start..], patterns);
+            if mask != 0 {
+                return Some(start + mask.trailing_zeros() as usize);
             }
-            Some(Expr::Value(v)) if enabled => {
-                res += v;
+            start += LANES;
+        }
+        (start..bytes.len()).find(|&i| patterns.iter().any(|p| bytes[i..].starts_with(p)))
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    mod sse2 {
+        use super::LANES;
+        use std::arch::x86_64::*;
+
+        /// Bitmask of the offsets in the first [`LANES`] bytes of `bytes` where any of `patterns`
+        /// starts, `bytes` must be long enough to hold each of them from the last offset
+        #[inline(always)]
+        pub fn prefix_mask(bytes: &[u8], patterns: &[&[u8]]) -> u32 {
+            // SAFETY: SSE2 is part of the x86_64 baseline, and loads at k..k + LANES are in
+            // bounds as checked for each pattern
+            unsafe {
+                let mut any = _mm_setzero_si128();
+                for pattern in patterns {
+                    assert!(bytes.len() >= LANES - 1 + pattern.len());
+                    let mut all = _mm_set1_epi8(-1);
+                    for (k, &c) in pattern.iter().enumerate() {
+                        let block = _mm_loadu_si128(bytes.as_ptr().add(k).cast());
+                        all = _mm_and_si128(all, _mm_cmpeq_epi8(block, _mm_set1_epi8(c as i8)));
+                    }
+                    any = _mm_or_si128(any, all);
+                }
+                _mm_movemask_epi8(any) as u32
             }
-            _ => {}
         }
-        input = &input[1..]
     }
-    Ok(res)
 }
 
-enum Expr {
-    Stop,
-    Start,
-    Value(u64),
+/// Parse mul function arguments with explicit pattern matching for every possible case
+#[inline(always)]
+fn parse_mul_args(args: &[u8]) -> Option<(usize, (u64, u64))> {
+    match args[4..] {
+        [l1, b',', r1, b')', ..] => {
+            Some((8, (num_from_digits!(u64, l1), num_from_digits!(u64, r1))))
+        }
+        [l1, b',', r1, r2, b')', ..] => Some((
+            9,
+            (num_from_digits!(u64, l1), num_from_digits!(u64, r1, r2)),
+        )),
+        [l1, b',', r1, r2, r3, b')', ..] => Some((
+            10,
+            (num_from_digits!(u64, l1), num_from_digits!(u64, r1, r2, r3)),
+        )),
+        [l1, l2, b',', r1, b')', ..] => Some((
+            9,
+            (num_from_digits!(u64, l1, l2), num_from_digits!(u64, r1)),
+        )),
+        [l1, l2, b',', r1, r2, b')', ..] => Some((
+            10,
+            (num_from_digits!(u64, l1, l2), num_from_digits!(u64, r1, r2)),
+        )),
+        [l1, l2, b',', r1, r2, r3, b')', ..] => Some((
+            11,
+            (
+                num_from_digits!(u64, l1, l2),
+                num_from_digits!(u64, r1, r2, r3),
+            ),
+        )),
+        [l1, l2, l3, b',', r1, b')', ..] => Some((
+            10,
+            (num_from_digits!(u64, l1, l2, l3), num_from_digits!(u64, r1)),
+        )),
+        [l1, l2, l3, b',', r1, r2, b')', ..] => Some((
+            11,
+            (
+                num_from_digits!(u64, l1, l2, l3),
+                num_from_digits!(u64, r1, r2),
+            ),
+        )),
+        [l1, l2, l3, b',', r1, r2, r3, b')', ..] => Some((
+            12,
+            (
+                num_from_digits!(u64, l1, l2, l3),
+                num_from_digits!(u64, r1, r2, r3),
+            ),
+        )),
+        _ => None,
+    }
 }
 
-fn parse_fn(expr: &str) -> Option<Expr> {
-    if expr.starts_with("mul(") {
-        let args = &expr[4..12];
-        let sep = args.find(',')?;
-        let l = &args[..sep];
-        let cp = args[sep + 1..].find(')')?;
-        let r = &args[sep + 1..sep + 1 + cp];
-        assert!(l.bytes().all(|b| b.is_ascii_digit()));
-        assert!(r.bytes().all(|b| b.is_ascii_digit()));
+/// Parse `mul(l,r)` at the start of `expr`, returns its length and arguments
+pub fn parse_mul(expr: &str) -> Option<(usize, (u64, u64))> {
+    let args = expr.strip_prefix("mul(")?.as_bytes();
+    let (l_len, l) = parse_mul_arg(args)?;
+    let args = args[l_len..].strip_prefix(b",")?;
+    let (r_len, r) = parse_mul_arg(args)?;
+    if args.get(r_len) != Some(&b')') {
+        return None;
+    }
+    Some((4 + l_len + 1 + r_len + 1, (l, r)))
+}
 
-        Some(Expr::Value(
-            u64_from_ascii(l.as_bytes()) * u64_from_ascii(r.as_bytes()),
-        ))
-    } else if expr.starts_with("do()") {
-        Some(Expr::Start)
-    } else if expr.starts_with("don't()") {
-        Some(Expr::Stop)
-    } else {
-        None
+/// Number of 1 to 3 digits at the start of `args`, returns its length and value
+fn parse_mul_arg(args: &[u8]) -> Option<(usize, u64)> {
+    let len = args
+        .iter()
+        .take(4)
+        .take_while(|c| c.is_ascii_digit())
+        .count();
+    if !(1..=3).contains(&len) {
+        return None;
     }
+    Some((len, u64_from_ascii(&args[..len])))
 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const DAY3_INPUT: &'static str = include_str!("../input/2024/day3.txt");
+    const DAY3_INPUT: &str = include_str!("../input/2024/day3.txt");
 
     #[test]
     fn part1_input() {
@@ -205,4 +435,200 @@ mod tests {
         assert_eq!(part2(DAY3_INPUT), 76911921);
         assert_eq!(part2_safe(DAY3_INPUT).unwrap(), 76911921);
     }
+
+    #[test]
+    fn example_instructions() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        let instructions = Tokenizer::optimistic(input).collect::<Vec<_>>();
+        let kinds = instructions.iter().map(|i| i.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                InstructionKind::Mul(2, 4),
+                InstructionKind::Dont,
+                InstructionKind::Mul(5, 5),
+                InstructionKind::Mul(11, 8),
+                InstructionKind::Do,
+                InstructionKind::Mul(8, 5),
+            ]
+        );
+        assert_eq!(instructions[0].span, 1..9);
+        assert_eq!(&input[instructions[1].span.clone()], "don't()");
+        assert_eq!(&input[instructions[3].span.clone()], "mul(11,8)");
+
+        assert_eq!(part1(input), 161);
+        assert_eq!(part2(input), 48);
+    }
+
+    #[test]
+    fn part1_simd_input() {
+        assert_eq!(part1_simd(DAY3_INPUT), 163931492);
+    }
+
+    #[test]
+    fn part2_simd_input() {
+        assert_eq!(part2_simd(DAY3_INPUT), 76911921);
+    }
+
+    #[test]
+    fn part2_parallel_input() {
+        assert_eq!(part2_parallel(DAY3_INPUT), 76911921);
+        for chunk_len in [1, 2, 3, 5, 7, 64, 1000, DAY3_INPUT.len()] {
+            assert_eq!(
+                sum_enabled_products_chunked(DAY3_INPUT, chunk_len),
+                76911921,
+                "{chunk_len}"
+            );
+        }
+    }
+
+    #[test]
+    fn example_chunked() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        for chunk_len in 0..=input.len() + 1 {
+            assert_eq!(
+                sum_enabled_products_chunked(input, chunk_len),
+                48,
+                "{chunk_len}"
+            );
+        }
+        assert_eq!(sum_enabled_products_chunked("", 4), 0);
+    }
+
+    #[test]
+    fn sparse_chunked() {
+        let input = format!(
+            "{}mul(123,456)don't(){}mul(2,3)do(){}mul(4,5)",
+            "x".repeat(100),
+            "é".repeat(50),
+            "ü".repeat(33)
+        );
+        for chunk_len in 1..=40 {
+            assert_eq!(
+                sum_enabled_products_chunked(&input, chunk_len),
+                123 * 456 + 4 * 5,
+                "{chunk_len}"
+            );
+        }
+    }
+
+    #[test]
+    fn segments_chain() {
+        let input = "mul(1,2)don't()mul(3,4)do()mul(5,6)don't()mul(7,8)";
+        let whole = Segment::evaluate(input, 0..input.len());
+        assert_eq!(
+            whole,
+            Segment {
+                products: [30, 30 + 2],
+                enabled: [false, false],
+            }
+        );
+        let head = Segment::evaluate(input, 0..10);
+        assert_eq!(head.products, [0, 2]);
+        assert_eq!(head.enabled, [false, false]);
+        let middle = Segment::evaluate(input, 10..27);
+        assert_eq!(middle.products, [0, 12]);
+        assert_eq!(middle.enabled, [true, true]);
+        let tail = Segment::evaluate(input, 27..input.len());
+        assert_eq!(tail.products, [0, 30]);
+        assert_eq!(head.then(middle).then(tail), whole);
+        assert_eq!(head.then(middle.then(tail)), whole);
+        assert_eq!(Segment::default().then(whole), whole);
+        assert_eq!(whole.then(Segment::default()), whole);
+    }
+
+    #[test]
+    fn tokenizers_agree() {
+        assert!(Tokenizer::new(DAY3_INPUT).eq(Tokenizer::optimistic(DAY3_INPUT)));
+        assert!(Tokenizer::new(DAY3_INPUT).eq(Tokenizer::vectorized(DAY3_INPUT)));
+
+        let muls = Tokenizer::new(DAY3_INPUT)
+            .filter(|i| matches!(i.kind, InstructionKind::Mul(..)))
+            .collect::<Vec<_>>();
+        for tokenizer in [
+            Tokenizer::new(DAY3_INPUT),
+            Tokenizer::optimistic(DAY3_INPUT),
+            Tokenizer::vectorized(DAY3_INPUT),
+        ] {
+            assert!(tokenizer.muls_only().eq(muls.iter().cloned()));
+        }
+    }
+
+    #[test]
+    fn find_prefixes_at_block_boundaries() {
+        let naive = |bytes: &[u8], patterns: &[&[u8]]| {
+            (0..bytes.len()).find(|&i| patterns.iter().any(|p| bytes[i..].starts_with(p)))
+        };
+        for len in 0..40 {
+            for at in 0..=len {
+                let mut bytes = vec![b'm'; len];
+                bytes.splice(at..at, b"mul(".iter().copied());
+                let bytes = &bytes[..bytes.len().min(len + 2)];
+                for patterns in [INSTRUCTION_PREFIXES, &[b"mul("], &[b"do()"]] {
+                    assert_eq!(
+                        scan::find_prefixes(bytes, patterns),
+                        naive(bytes, patterns),
+                        "{:?} in {:?}",
+                        patterns,
+                        std::str::from_utf8(bytes)
+                    );
+                }
+            }
+        }
+        assert_eq!(scan::find_prefixes(b"xxdo", INSTRUCTION_PREFIXES), Some(2));
+        assert_eq!(scan::find_prefixes(b"mul(", &[]), None);
+    }
+
+    #[test]
+    fn example_safe() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        assert!(Tokenizer::new(input).eq(Tokenizer::optimistic(input)));
+        assert_eq!(part1_safe(input).unwrap(), 161);
+        assert_eq!(part2_safe(input).unwrap(), 48);
+    }
+
+    #[test]
+    fn end_of_input() {
+        for (input, p1, p2) in [
+            ("mul(1,2)", 2, 2),
+            ("xmul(1,2)", 2, 2),
+            ("mul(123,456)", 56088, 56088),
+            ("mul(1,2", 0, 0),
+            ("mul(1,", 0, 0),
+            ("mul(1", 0, 0),
+            ("mul(", 0, 0),
+            ("mul", 0, 0),
+            ("m", 0, 0),
+            ("", 0, 0),
+            ("don't()mul(1,2)", 2, 0),
+            ("don't()do()mul(1,2)", 2, 2),
+            ("mul(3,4)don't()do(", 12, 12),
+            ("mul(2,3)\u{fc}mul(4,5\u{fc}", 6, 6),
+        ] {
+            assert_eq!(part1_safe(input).unwrap(), p1, "{input:?}");
+            assert_eq!(part2_safe(input).unwrap(), p2, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        for input in [
+            "mul(1a,2)",
+            "mul(1,2a)",
+            "mul(a1,2)",
+            "mul(1234,5)",
+            "mul(5,1234)",
+            "mul(,5)",
+            "mul(5,)",
+            "mul( 1,2)",
+            "mul(-1,2)",
+            "mul(1.2)",
+        ] {
+            assert_eq!(parse_mul(input), None, "{input:?}");
+            assert_eq!(part1_safe(input).unwrap(), 0, "{input:?}");
+        }
+        assert_eq!(parse_mul("mul(12,345)x"), Some((11, (12, 345))));
+        // A rejected `mul(` does not hide an instruction starting inside it
+        assert_eq!(part1_safe("mul(mul(2,3)").unwrap(), 6);
+    }
 }
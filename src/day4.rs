@@ -1,131 +1,279 @@
+use crate::grid::{Direction, Grid, Highlight, Pattern, PatternMatch, Pos, Symmetry};
 use anyhow::Result;
 use aoc_runner_derive::aoc;
-
-const WIDTH: usize = 141;
-const HEIGHT: usize = 140;
+use std::fmt::Write;
 
 const X: u8 = b'X';
 const M: u8 = b'M';
 const A: u8 = b'A';
 const S: u8 = b'S';
 
-#[inline(always)]
-fn get_idx(row: Option<usize>, col: Option<usize>) -> Option<usize> {
-    match (row, col) {
-        (Some(row), Some(col)) => Some(row * WIDTH + col),
-        _ => None,
-    }
-}
+/// Word to look for in part 1
+pub const XMAS: &[u8] = b"XMAS";
+/// Shape to look for in part 2 in any rotation, `.` cells can hold anything
+pub const X_MAS: &[u8] = b"M.S\n.A.\nM.S";
 
 #[aoc(day4, part1, AoCS)]
 pub fn part1(input: &str) -> u32 {
-    let input = input.as_bytes();
+    count_xmas(&Grid::parse_unchecked(input.as_bytes()))
+}
 
-    let mut res = 0;
+#[aoc(day4, part1, default)]
+pub fn part1_safe(input: &str) -> Result<u32> {
+    let grid = Grid::parse(input.as_bytes())?;
+    Ok(count_xmas(&grid))
+}
 
-    for row in 0..HEIGHT {
-        for col in 0..WIDTH - 1 {
-            if input[row * WIDTH + col] == X {
-                if let Some(&S) =
-                    get_idx(Some(row), col.checked_sub(3)).and_then(|idx| input.get(idx))
-                {
-                    let a = input[row * WIDTH + col - 2];
-                    let m = input[row * WIDTH + col - 1];
-                    if (m, a) == (M, A) {
-                        res += 1;
-                    }
-                }
-
-                if let Some(&S) = input.get(row * WIDTH + col + 3) {
-                    let a = input[row * WIDTH + col + 2];
-                    let m = input[row * WIDTH + col + 1];
-                    if (m, a) == (M, A) {
-                        res += 1;
-                    }
-                };
-
-                // --------------
-
-                if let Some(&S) =
-                    get_idx(row.checked_sub(3), col.checked_sub(3)).and_then(|idx| input.get(idx))
-                {
-                    let a = input[(row - 2) * WIDTH + col - 2];
-                    let m = input[(row - 1) * WIDTH + col - 1];
-                    if (m, a) == (M, A) {
-                        res += 1;
-                    }
-                };
-
-                if let Some(&S) =
-                    get_idx(row.checked_sub(3), Some(col)).and_then(|idx| input.get(idx))
-                {
-                    let a = input[(row - 2) * WIDTH + col];
-                    let m = input[(row - 1) * WIDTH + col];
-                    if (m, a) == (M, A) {
-                        res += 1;
-                    }
-                };
-
-                if let Some(&S) =
-                    get_idx(row.checked_sub(3), Some(col + 3)).and_then(|idx| input.get(idx))
-                {
-                    let a = input[(row - 2) * WIDTH + col + 2];
-                    let m = input[(row - 1) * WIDTH + col + 1];
-                    if (m, a) == (M, A) {
-                        res += 1;
-                    }
-                };
-
-                // --------------
-
-                if let Some(&S) =
-                    get_idx(Some(row + 3), col.checked_sub(3)).and_then(|idx| input.get(idx))
-                {
-                    let a = input[(row + 2) * WIDTH + col - 2];
-                    let m = input[(row + 1) * WIDTH + col - 1];
-                    if (m, a) == (M, A) {
-                        res += 1;
-                    }
-                };
-
-                if let Some(&S) = input.get((row + 3) * WIDTH + col) {
-                    let a = input[(row + 2) * WIDTH + col];
-                    let m = input[(row + 1) * WIDTH + col];
-                    if (m, a) == (M, A) {
-                        res += 1;
-                    }
-                };
-
-                if let Some(&S) = input.get((row + 3) * WIDTH + col + 3) {
-                    let a = input[(row + 2) * WIDTH + col + 2];
-                    let m = input[(row + 1) * WIDTH + col + 1];
-                    if (m, a) == (M, A) {
-                        res += 1;
-                    }
-                };
+#[aoc(day4, part1, bitboard)]
+pub fn part1_bitboard(input: &str) -> u32 {
+    count_xmas_bitboard(&Grid::parse_unchecked(input.as_bytes()))
+}
+
+/// Same as [`count_xmas`], with all cells of a row checked at once on bitmasks
+pub fn count_xmas_bitboard(grid: &Grid<u8, &[u8]>) -> u32 {
+    let words = grid.width().div_ceil(64);
+    let [x, m, a, s] = [X, M, A, S].map(|letter| letter_masks(grid, letter, words));
+
+    let mut res = 0;
+    for dir in Direction::ALL {
+        let (d_row, d_col) = dir.offset();
+        for row in 0..grid.height() {
+            // Once the last letter fits, so do all the ones before
+            if row
+                .checked_add_signed(3 * d_row)
+                .is_none_or(|last_row| last_row >= grid.height())
+            {
+                continue;
+            }
+            // Masks of the `n`th letter's row, with cells lined up on the X they follow
+            let letter = |masks: &[u64], n: isize, word: usize| {
+                let start = row.wrapping_add_signed(n * d_row) * words;
+                shifted_word(&masks[start..start + words], word, n * d_col)
+            };
+            for word in 0..words {
+                let found = x[row * words + word]
+                    & letter(&m, 1, word)
+                    & letter(&a, 2, word)
+                    & letter(&s, 3, word);
+                res += found.count_ones();
             }
         }
     }
+    res
+}
+
+/// Bitmask of the cells holding `letter`, with `words` words per row and bit `col % 64` of word
+/// `col / 64` standing for column `col`
+fn letter_masks(grid: &Grid<u8, &[u8]>, letter: u8, words: usize) -> Vec<u64> {
+    let mut masks = vec![0; grid.height() * words];
+    for (row, cells) in grid.rows().enumerate() {
+        for (word, chunk) in cells.chunks(64).enumerate() {
+            masks[row * words + word] = eq_mask(chunk, letter);
+        }
+    }
+    masks
+}
+
+/// Bitmask of the bytes of `chunk`, at most 64 of them, equal to `letter`
+#[inline(always)]
+fn eq_mask(chunk: &[u8], letter: u8) -> u64 {
+    #[cfg(target_arch = "x86_64")]
+    if let Ok(chunk) = <&[u8; 64]>::try_from(chunk) {
+        use std::arch::x86_64::*;
+        // SAFETY: SSE2 is part of the x86_64 baseline, and the 4 loads cover exactly the 64 bytes
+        return unsafe {
+            let letter = _mm_set1_epi8(letter as i8);
+            (0..4).fold(0, |mask, i| {
+                let block = _mm_loadu_si128(chunk.as_ptr().add(16 * i).cast());
+                let found = _mm_movemask_epi8(_mm_cmpeq_epi8(block, letter)) as u16;
+                mask | (found as u64) << (16 * i)
+            })
+        };
+    }
+    chunk
+        .iter()
+        .enumerate()
+        .fold(0, |mask, (i, &c)| mask | ((c == letter) as u64) << i)
+}
 
+/// Word `word` of the row bitmask `row` shifted so that bit `col` holds bit `col + shift`, bits
+/// outside of the row are unset
+#[inline(always)]
+fn shifted_word(row: &[u64], word: usize, shift: isize) -> u64 {
+    let get = |word: Option<usize>| word.and_then(|word| row.get(word)).copied().unwrap_or(0);
+    let bits = shift.unsigned_abs() as u32;
+    match shift {
+        0 => row[word],
+        1.. => (row[word] >> bits) | get(Some(word + 1)) << (64 - bits),
+        _ => (row[word] << bits) | get(word.checked_sub(1)) >> (64 - bits),
+    }
+}
+
+/// Occurrence of a word in a grid, read from `start` towards `dir`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WordMatch {
+    pub start: Pos,
+    pub dir: Direction,
+}
+
+impl WordMatch {
+    /// Positions of the letters of a word of `len` letters
+    pub fn cells(&self, len: usize) -> impl Iterator<Item = Pos> + '_ {
+        (0..len).filter_map(|n| self.start.step_by(self.dir, n))
+    }
+}
+
+/// Every occurrence of `word` written in any of the eight directions, by start position in
+/// row-major order then clockwise from [`Direction::Up`].
+///
+/// Palindromes are found once from each end, words of a single letter only once per cell.
+pub fn find_word<'a>(
+    grid: &'a Grid<u8, &'a [u8]>,
+    word: &'a [u8],
+) -> impl Iterator<Item = WordMatch> + 'a {
+    let dirs: &[Direction] = if word.len() > 1 {
+        &Direction::ALL
+    } else {
+        &[Direction::Right]
+    };
+    grid.iter()
+        .filter(move |&(_, &c)| Some(&c) == word.first())
+        .flat_map(move |(start, _)| dirs.iter().map(move |&dir| WordMatch { start, dir }))
+        .filter(move |m| {
+            grid.ray(m.start, m.dir)
+                .map(|pos| grid[pos])
+                .take(word.len())
+                .eq(word.iter().copied())
+        })
+}
+
+/// Number of occurrences of `word` in any of the eight directions, see [`find_word`]
+pub fn count_word(grid: &Grid<u8, &[u8]>, word: &[u8]) -> usize {
+    find_word(grid, word).count()
+}
+
+/// Same as [`count_word`] with [`XMAS`], specialized for it
+pub fn count_xmas(grid: &Grid<u8, &[u8]>) -> u32 {
+    let (width, height, stride) = (grid.width(), grid.height(), grid.stride());
+    let cells = grid.as_slice();
+    // Words running past the right side of the grid go through a line break, only the left side,
+    // the top and the bottom need checking
+    debug_assert!(stride > width, "rows must be followed by a line break");
+    let at = |row: Option<usize>, col: Option<usize>| Some(row? * stride + col?);
+    let step = stride as isize;
+
+    let mut res = 0;
+    for row in 0..height {
+        let (up, down) = (row.checked_sub(3), Some(row + 3));
+        for col in 0..width {
+            let idx = row * stride + col;
+            if cells[idx] != X {
+                continue;
+            }
+            let (left, right) = (col.checked_sub(3), Some(col + 3));
+            // Spelled out since looping over directions is much slower
+            res += xmas_towards(cells, idx, at(up, Some(col)), -step)
+                + xmas_towards(cells, idx, at(up, right), 1 - step)
+                + xmas_towards(cells, idx, at(Some(row), right), 1)
+                + xmas_towards(cells, idx, at(down, right), step + 1)
+                + xmas_towards(cells, idx, at(down, Some(col)), step)
+                + xmas_towards(cells, idx, at(down, left), step - 1)
+                + xmas_towards(cells, idx, at(Some(row), left), -1)
+                + xmas_towards(cells, idx, at(up, left), -step - 1);
+        }
+    }
     res
 }
 
+/// Whether the `X` at `idx` starts an XMAS going `step` cells at a time and ending at `last`
+#[inline(always)]
+fn xmas_towards(cells: &[u8], idx: usize, last: Option<usize>, step: isize) -> u32 {
+    let letter = |n: isize| cells[idx.wrapping_add_signed(n * step)];
+    // Once the last letter fits, so do all the ones before
+    match last.and_then(|last| cells.get(last)) {
+        Some(&S) if (letter(1), letter(2)) == (M, A) => 1,
+        _ => 0,
+    }
+}
+
 #[aoc(day4, part2, AoCS)]
 pub fn part2(input: &str) -> u64 {
+    count_x_mas(&Grid::parse_unchecked(input.as_bytes()))
+}
+
+#[aoc(day4, part2, default)]
+pub fn part2_safe(input: &str) -> Result<u64> {
+    let grid = Grid::parse(input.as_bytes())?;
+    Ok(x_mas_pattern().count(&grid) as u64)
+}
+
+/// [`X_MAS`] as a pattern, whose variant 0 is the template as written
+pub fn x_mas_pattern() -> Pattern<u8> {
+    Pattern::parse(X_MAS, b'.', Symmetry::Rotations).unwrap()
+}
+
+/// Centres of every X-MAS, in row-major order
+pub fn find_x_mas(grid: &Grid<u8, &[u8]>) -> Vec<Pos> {
+    x_mas_pattern().find(grid).map(x_mas_centre).collect()
+}
+
+/// Centre of the X-MAS placed at `m`, the middle of its 3x3 variant
+fn x_mas_centre(m: PatternMatch) -> Pos {
+    Pos::new(m.pos.row + 1, m.pos.col + 1)
+}
+
+/// Grid with the letters of every XMAS highlighted, followed by where each starts and its direction
+pub fn explain_xmas(input: &str, highlight: Highlight) -> Result<String> {
+    let grid = Grid::parse(input.as_bytes())?;
+    let matches = find_word(&grid, XMAS).collect::<Vec<_>>();
+    let mut marked = Grid::new(grid.width(), grid.height(), false);
+    for m in &matches {
+        for pos in m.cells(XMAS.len()) {
+            marked[pos] = true;
+        }
+    }
+
+    let mut res = grid.render(&marked, highlight);
+    for m in matches {
+        writeln!(res, "{},{}: {:?}", m.start.row, m.start.col, m.dir)?;
+    }
+    Ok(res)
+}
+
+/// Grid with the letters of every X-MAS highlighted, followed by the centre of each
+pub fn explain_x_mas(input: &str, highlight: Highlight) -> Result<String> {
+    let grid = Grid::parse(input.as_bytes())?;
+    let pattern = x_mas_pattern();
+    let matches = pattern.find(&grid).collect::<Vec<_>>();
+    let mut marked = Grid::new(grid.width(), grid.height(), false);
+    for &m in &matches {
+        for pos in pattern.cells(m) {
+            marked[pos] = true;
+        }
+    }
+
+    let mut res = grid.render(&marked, highlight);
+    for centre in matches.into_iter().map(x_mas_centre) {
+        writeln!(res, "{},{}", centre.row, centre.col)?;
+    }
+    Ok(res)
+}
+
+/// Number of MAS crossing each other on the diagonals, only valid if the grid is made of XMAS
+/// letters
+pub fn count_x_mas(grid: &Grid<u8, &[u8]>) -> u64 {
     const X_MAS_SUM: u16 = 2 * M as u16 + 2 * S as u16;
-    let input = input.as_bytes();
     let mut res = 0;
+    if grid.height() < 3 {
+        return res;
+    }
 
-    // Hint to compiler that input size is known
-    input.get(WIDTH * HEIGHT - 2).unwrap();
-
-    let mut top = &input[..WIDTH - 1];
-    let mut cur = &input[WIDTH..2 * WIDTH - 1];
-    let mut bot;
+    let mut rows = grid.rows();
+    let mut top = rows.next().unwrap();
+    let mut cur = rows.next().unwrap();
 
-    for row in (2 * WIDTH..WIDTH * HEIGHT).step_by(WIDTH) {
-        bot = &input[row..row + WIDTH - 1];
-        for col in 1..WIDTH - 2 {
+    for bot in rows {
+        for col in 1..grid.width() - 1 {
             let tl = top[col - 1] as u16;
             let tr = top[col + 1] as u16;
             let bl = bot[col - 1] as u16;
@@ -148,22 +296,19 @@ pub fn part2(input: &str) -> u64 {
 #[aoc(day4, part2, original)]
 pub fn part2_original(input: &str) -> u64 {
     const X_MAS_SUM: u16 = 2 * M as u16 + 2 * S as u16;
-    let input = input.as_bytes();
+    let grid = Grid::parse(input.as_bytes()).unwrap();
     let mut res = 0;
 
-    // Hint to compiler that input size is known
-    input.get(WIDTH * HEIGHT - 2).unwrap();
-
-    for i in WIDTH + 1..WIDTH * (HEIGHT - 1) - 2 {
-        let tl = input[i - WIDTH - 1] as u16;
-        let tr = input[i - WIDTH + 1] as u16;
-        let bl = input[i + WIDTH - 1] as u16;
-        let br = input[i + WIDTH + 1] as u16;
+    for (pos, &c) in grid.iter() {
+        let corners = Direction::DIAGONAL.map(|dir| pos.step(dir).and_then(|pos| grid.get(pos)));
+        let [Some(&tr), Some(&br), Some(&bl), Some(&tl)] = corners else {
+            continue;
+        };
+        let (tl, tr, bl, br) = (tl as u16, tr as u16, bl as u16, br as u16);
         // Since input letters are very limited, just summing them all up is enough to check all MAS combinations
         let v = (tl + tr + bl + br == X_MAS_SUM && tl != br) as u64;
 
-        // Don't ask lol, moving if here gives 350% boost
-        if input[i] == A {
+        if c == A {
             res += v;
         }
     }
@@ -175,15 +320,192 @@ pub fn part2_original(input: &str) -> u64 {
 mod tests {
     use super::*;
 
-    const DAY4_INPUT: &'static str = include_str!("../input/2024/day4.txt");
+    const DAY4_INPUT: &str = include_str!("../input/2024/day4.txt");
 
     #[test]
     fn part1_input() {
         assert_eq!(part1(DAY4_INPUT), 2464);
+        assert_eq!(part1_safe(DAY4_INPUT).unwrap(), 2464);
+        assert_eq!(part1_bitboard(DAY4_INPUT), 2464);
     }
 
     #[test]
     fn part2_input() {
         assert_eq!(part2(DAY4_INPUT), 1982);
+        assert_eq!(part2_safe(DAY4_INPUT).unwrap(), 1982);
+        assert_eq!(part2_original(DAY4_INPUT), 1982);
+    }
+
+    #[test]
+    fn example() {
+        let input = "MMMSXXMASM
+MSAMXMSMSA
+AMXSXMAAMM
+MSAMASMSMX
+XMASAMXAMM
+XXAMMXXAMA
+SMSMSASXSS
+SAXAMASAAA
+MAMMMXMMMM
+MXMXAXMASX
+";
+        assert_eq!(part1_safe(input).unwrap(), 18);
+        assert_eq!(part1_bitboard(input), 18);
+        let grid = Grid::parse(input.as_bytes()).unwrap();
+        assert_eq!(count_word(&grid, XMAS), 18);
+        assert_eq!(count_word(&grid, b"SAMX"), 18);
+        assert_eq!(part2_safe(input).unwrap(), 9);
+        assert_eq!(part2_original(input), 9);
+    }
+
+    #[test]
+    fn count_word_input() {
+        let grid = Grid::parse(DAY4_INPUT.as_bytes()).unwrap();
+        assert_eq!(count_word(&grid, XMAS), 2464);
+    }
+
+    #[test]
+    fn find_words() {
+        let grid = Grid::parse(b"XMASAMX\n").unwrap();
+        assert_eq!(
+            find_word(&grid, XMAS).collect::<Vec<_>>(),
+            [
+                WordMatch {
+                    start: Pos::new(0, 0),
+                    dir: Direction::Right
+                },
+                WordMatch {
+                    start: Pos::new(0, 6),
+                    dir: Direction::Left
+                },
+            ]
+        );
+        assert_eq!(count_word(&grid, b"MASAM"), 2);
+        assert_eq!(count_word(&grid, b"XMASAMXM"), 0);
+        assert_eq!(count_word(&grid, b"A"), 2);
+        assert_eq!(count_word(&grid, b""), 0);
+    }
+
+    #[test]
+    fn palindromes() {
+        let grid = Grid::parse(b"ABA\nBAB\nABA\n").unwrap();
+        // Rows and columns on the edges, read both ways
+        assert_eq!(count_word(&grid, b"ABA"), 8);
+        // Middle row and column, read both ways
+        assert_eq!(count_word(&grid, b"BAB"), 4);
+        // Both diagonals, read both ways
+        assert_eq!(count_word(&grid, b"AAA"), 4);
+        assert_eq!(count_word(&grid, b"AA"), 8);
+        let matches = find_word(&grid, b"AAA").collect::<Vec<_>>();
+        assert_eq!(matches[0].start, Pos::new(0, 0));
+        assert_eq!(matches[0].dir, Direction::DownRight);
+        assert_eq!(matches[3].start, Pos::new(2, 2));
+        assert_eq!(matches[3].dir, Direction::UpLeft);
+    }
+
+    #[test]
+    fn example_explained() {
+        let input = "..X...
+.SAMX.
+.A..A.
+XMAS.S
+.X....
+";
+        let grid = Grid::parse(input.as_bytes()).unwrap();
+        assert_eq!(
+            find_word(&grid, XMAS).collect::<Vec<_>>(),
+            [
+                WordMatch {
+                    start: Pos::new(0, 2),
+                    dir: Direction::DownRight
+                },
+                WordMatch {
+                    start: Pos::new(1, 4),
+                    dir: Direction::Left
+                },
+                WordMatch {
+                    start: Pos::new(3, 0),
+                    dir: Direction::Right
+                },
+                WordMatch {
+                    start: Pos::new(4, 1),
+                    dir: Direction::Up
+                },
+            ]
+        );
+        assert_eq!(
+            explain_xmas(input, Highlight::Dots).unwrap(),
+            "..X...
+.SAMX.
+.A..A.
+XMAS.S
+.X....
+0,2: DownRight
+1,4: Left
+3,0: Right
+4,1: Up
+"
+        );
+
+        let input = "M.S.
+.A..
+M.SX
+";
+        let grid = Grid::parse(input.as_bytes()).unwrap();
+        assert_eq!(find_x_mas(&grid), [Pos::new(1, 1)]);
+        assert_eq!(
+            explain_x_mas(input, Highlight::Dots).unwrap(),
+            "M.S.
+.A..
+M.S.
+1,1
+"
+        );
+    }
+
+    #[test]
+    fn bitboard_word_boundaries() {
+        // Words crossing from one 64 bit word of a row mask into the next
+        for width in [4, 63, 64, 65, 66, 67, 127, 128, 130] {
+            for col in 0..=width - 4 {
+                let mut row = vec![b'.'; width];
+                row[col..col + 4].copy_from_slice(XMAS);
+                let mut input = String::from_utf8(row.clone()).unwrap() + "\n";
+                row[col..col + 4].copy_from_slice(b"SAMX");
+                input += &(String::from_utf8(row).unwrap() + "\n");
+                let grid = Grid::parse(input.as_bytes()).unwrap();
+                assert_eq!(count_xmas_bitboard(&grid), 2, "{width} {col}");
+                assert_eq!(count_xmas_bitboard(&grid), count_xmas(&grid));
+            }
+        }
+        let input = format!("{}XMAS\n{}SAMX\n", ".".repeat(62), ".".repeat(62));
+        let grid = Grid::parse(input.as_bytes()).unwrap();
+        assert_eq!(count_xmas_bitboard(&grid), count_xmas(&grid));
+    }
+
+    #[test]
+    fn words_stop_at_edges() {
+        // Each would spell XMAS if rows were joined end to end
+        for input in ["..XM\nAS..\n", "..SA\nMX..\n"] {
+            for input in [input.to_string(), input.replace('\n', "\r\n")] {
+                let grid = Grid::parse(input.as_bytes()).unwrap();
+                assert_eq!(count_xmas(&grid), 0, "{input:?}");
+                assert_eq!(part1(&input), 0, "{input:?}");
+                assert_eq!(count_xmas_bitboard(&grid), 0, "{input:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn small_grids() {
+        assert_eq!(part1_safe("XMAS").unwrap(), 1);
+        assert_eq!(part1_safe("S\nA\nM\nX\n").unwrap(), 1);
+        assert_eq!(part2_safe("MAS").unwrap(), 0);
+        assert_eq!(part2_safe("M.S\n.A.\nM.S").unwrap(), 1);
+        assert_eq!(part2_safe("S.S\n.A.\nM.M").unwrap(), 1);
+        assert_eq!(part2_safe("S.M\n.A.\nM.S").unwrap(), 0);
+        // Letter sums match X-MAS, but not the letters themselves
+        assert_eq!(part2_safe("O.Q\n.A.\nO.Q").unwrap(), 0);
+        assert!(part1_safe("XMAS\nXMA\n").is_err());
     }
 }
@@ -73,7 +73,7 @@ fn parse(input: &[u8]) -> (Vec<(u32, u32)>, usize) {
 mod tests {
     use super::*;
 
-    const DAY5_INPUT: &'static str = include_str!("../input/2024/day5.txt");
+    const DAY5_INPUT: &str = include_str!("../input/2024/day5.txt");
 
     #[test]
     fn part1_input() {
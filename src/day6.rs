@@ -0,0 +1,309 @@
+use crate::grid::{Direction, Grid, Pos};
+use anyhow::{anyhow, bail, ensure, Result};
+use aoc_runner_derive::aoc;
+use rayon::prelude::*;
+
+#[aoc(day6, part1, AoCS)]
+pub fn part1(input: &str) -> usize {
+    part1_safe(input).unwrap()
+}
+
+#[aoc(day6, part1, default)]
+pub fn part1_safe(input: &str) -> Result<usize> {
+    let lab = Lab::parse(input)?;
+    let path = lab.patrol()?;
+    let mut visited = Grid::new(lab.obstructed.width(), lab.obstructed.height(), false);
+    Ok(path
+        .iter()
+        .filter(|guard| !std::mem::replace(&mut visited[guard.pos], true))
+        .count())
+}
+
+#[aoc(day6, part2, AoCS)]
+pub fn part2(input: &str) -> usize {
+    let lab = Lab::parse(input).unwrap();
+    let jumps = Jumps::new(&lab);
+    let (width, height) = (lab.obstructed.width(), lab.obstructed.height());
+    lab.obstruction_candidates(&lab.patrol().unwrap())
+        .par_iter()
+        .map_init(
+            || (Grid::new(width, height, 0), Vec::new()),
+            |(seen, touched), &(from, obstruction)| jumps.loops(from, obstruction, seen, touched),
+        )
+        .filter(|&loops| loops)
+        .count()
+}
+
+#[aoc(day6, part2, default)]
+pub fn part2_safe(input: &str) -> Result<usize> {
+    let lab = Lab::parse(input)?;
+    let path = lab.patrol()?;
+    Ok(lab
+        .obstruction_candidates(&path)
+        .into_iter()
+        .filter(|&(from, obstruction)| lab.loops(from, obstruction))
+        .count())
+}
+
+/// Position and heading of the guard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Guard {
+    pub pos: Pos,
+    pub dir: Direction,
+}
+
+/// Map of the lab and where the guard starts
+#[derive(Debug, Clone)]
+pub struct Lab {
+    pub obstructed: Grid<bool>,
+    pub guard: Guard,
+}
+
+impl Lab {
+    pub fn parse(input: &str) -> Result<Self> {
+        let grid = Grid::parse(input.as_bytes())?;
+        let mut guard = None;
+        for (pos, &c) in grid.iter() {
+            let dir = match c {
+                b'.' | b'#' => continue,
+                b'^' => Direction::Up,
+                b'>' => Direction::Right,
+                b'v' => Direction::Down,
+                b'<' => Direction::Left,
+                _ => bail!(
+                    "row {}, col {}: unexpected {:?}",
+                    pos.row + 1,
+                    pos.col + 1,
+                    c as char
+                ),
+            };
+            ensure!(guard.is_none(), "more than one guard");
+            guard = Some(Guard { pos, dir });
+        }
+
+        Ok(Lab {
+            obstructed: grid.map(|_, &c| c == b'#'),
+            guard: guard.ok_or_else(|| anyhow!("no guard"))?,
+        })
+    }
+
+    /// State of the guard after one move, either forward or turning right in front of an
+    /// obstruction, `None` once it leaves the map
+    #[inline(always)]
+    pub fn step(&self, guard: Guard, extra: Option<Pos>) -> Option<Guard> {
+        let ahead = guard.pos.step(guard.dir)?;
+        if *self.obstructed.get(ahead)? || Some(ahead) == extra {
+            Some(Guard {
+                pos: guard.pos,
+                dir: guard.dir.turn_right(),
+            })
+        } else {
+            Some(Guard {
+                pos: ahead,
+                dir: guard.dir,
+            })
+        }
+    }
+
+    /// Every state of the guard from the start until it leaves the map
+    pub fn patrol(&self) -> Result<Vec<Guard>> {
+        let mut seen = Grid::new(self.obstructed.width(), self.obstructed.height(), 0);
+        let mut path = vec![];
+        let mut guard = Some(self.guard);
+        while let Some(cur) = guard {
+            ensure!(
+                seen[cur.pos] & dir_bit(cur.dir) == 0,
+                "guard walks in a loop"
+            );
+            seen[cur.pos] |= dir_bit(cur.dir);
+            path.push(cur);
+            guard = self.step(cur, None);
+        }
+        Ok(path)
+    }
+
+    /// Positions on `path` where a new obstruction changes it, with the state of the guard right
+    /// before it would first walk there.
+    ///
+    /// Starting from there gives the same result as starting from the beginning, since the
+    /// guard never came across the obstruction before.
+    pub fn obstruction_candidates(&self, path: &[Guard]) -> Vec<(Guard, Pos)> {
+        let mut visited = Grid::new(self.obstructed.width(), self.obstructed.height(), false);
+        visited[self.guard.pos] = true;
+        path.windows(2)
+            .filter(|states| !std::mem::replace(&mut visited[states[1].pos], true))
+            .map(|states| (states[0], states[1].pos))
+            .collect()
+    }
+
+    /// Whether the guard walks in a loop from `from` once `obstruction` is added
+    pub fn loops(&self, from: Guard, obstruction: Pos) -> bool {
+        let mut seen = Grid::new(self.obstructed.width(), self.obstructed.height(), 0);
+        let mut guard = Some(from);
+        while let Some(cur) = guard {
+            if seen[cur.pos] & dir_bit(cur.dir) != 0 {
+                return true;
+            }
+            seen[cur.pos] |= dir_bit(cur.dir);
+            guard = self.step(cur, Some(obstruction));
+        }
+        false
+    }
+}
+
+/// Distinct bit for each of the orthogonal directions the guard can face
+#[inline(always)]
+fn dir_bit(dir: Direction) -> u8 {
+    1 << (dir as u8 / 2)
+}
+
+/// Where the guard stops walking straight from each cell in each direction, `None` if it leaves
+/// the map, so that loops are checked one straight line at a time
+struct Jumps {
+    stops: Grid<[Option<Pos>; 4]>,
+}
+
+impl Jumps {
+    fn new(lab: &Lab) -> Self {
+        let map = &lab.obstructed;
+        let mut stops = Grid::new(map.width(), map.height(), [None; 4]);
+        let positions = map.positions().collect::<Vec<_>>();
+        for dir in Direction::ORTHOGONAL {
+            // The cell ahead must be done first
+            let order: Box<dyn Iterator<Item = &Pos>> = match dir {
+                Direction::Up | Direction::Left => Box::new(positions.iter()),
+                _ => Box::new(positions.iter().rev()),
+            };
+            for &pos in order {
+                let ahead = pos.step(dir).filter(|&ahead| map.contains(ahead));
+                stops[pos][dir as usize / 2] = match ahead {
+                    Some(ahead) if map[ahead] => Some(pos),
+                    Some(ahead) => stops[ahead][dir as usize / 2],
+                    None => None,
+                };
+            }
+        }
+        Jumps { stops }
+    }
+
+    /// Where `guard` stops walking straight once `obstruction` is added
+    #[inline(always)]
+    fn stop(&self, guard: Guard, obstruction: Pos) -> Option<Pos> {
+        let Guard { pos, dir } = guard;
+        let stop = self.stops[pos][dir as usize / 2];
+        let ahead = match dir {
+            Direction::Up => obstruction.col == pos.col && obstruction.row < pos.row,
+            Direction::Down => obstruction.col == pos.col && obstruction.row > pos.row,
+            Direction::Left => obstruction.row == pos.row && obstruction.col < pos.col,
+            _ => obstruction.row == pos.row && obstruction.col > pos.col,
+        };
+        if !ahead {
+            return stop;
+        }
+        let distance = |to: Pos| pos.row.abs_diff(to.row) + pos.col.abs_diff(to.col);
+        let before_obstruction = distance(obstruction) - 1;
+        match stop {
+            Some(stop) if distance(stop) <= before_obstruction => Some(stop),
+            _ => pos.step_by(dir, before_obstruction),
+        }
+    }
+
+    /// Same as [`Lab::loops`], `seen` must be all zeros and is left that way using `touched`
+    fn loops(
+        &self,
+        from: Guard,
+        obstruction: Pos,
+        seen: &mut Grid<u8>,
+        touched: &mut Vec<Pos>,
+    ) -> bool {
+        let mut guard = from;
+        let res = loop {
+            let Some(stop) = self.stop(guard, obstruction) else {
+                break false;
+            };
+            let bit = dir_bit(guard.dir);
+            if seen[stop] & bit != 0 {
+                break true;
+            }
+            seen[stop] |= bit;
+            touched.push(stop);
+            guard = Guard {
+                pos: stop,
+                dir: guard.dir.turn_right(),
+            };
+        };
+        for pos in touched.drain(..) {
+            seen[pos] = 0;
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Random lab from `input/generated`, not a puzzle input, with no known answers
+    const DAY6_GENERATED: &str = include_str!("../input/generated/day6.txt");
+    const DAY6_EXAMPLE: &str = "\
+....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#..^.....
+........#.
+#.........
+......#...
+";
+
+    #[test]
+    fn part1_example() {
+        assert_eq!(part1(DAY6_EXAMPLE), 41);
+        assert_eq!(part1_safe(DAY6_EXAMPLE).unwrap(), 41);
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_eq!(part2(DAY6_EXAMPLE), 6);
+        assert_eq!(part2_safe(DAY6_EXAMPLE).unwrap(), 6);
+    }
+
+    #[test]
+    fn part2_generated() {
+        assert_eq!(part2(DAY6_GENERATED), part2_safe(DAY6_GENERATED).unwrap());
+    }
+
+    #[test]
+    fn loop_candidates() {
+        let lab = Lab::parse(DAY6_EXAMPLE).unwrap();
+        let path = lab.patrol().unwrap();
+        let jumps = Jumps::new(&lab);
+        let mut seen = Grid::new(10, 10, 0);
+        let mut touched = vec![];
+        let mut loops = vec![];
+        for (from, obstruction) in lab.obstruction_candidates(&path) {
+            let fast = jumps.loops(from, obstruction, &mut seen, &mut touched);
+            assert_eq!(fast, lab.loops(from, obstruction), "{obstruction:?}");
+            if fast {
+                loops.push((obstruction.row, obstruction.col));
+            }
+        }
+        loops.sort();
+        assert_eq!(loops, [(6, 3), (7, 6), (7, 7), (8, 1), (8, 3), (9, 7)]);
+        assert!(seen.iter().all(|(_, &bits)| bits == 0));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(part1_safe("..\n..\n").is_err());
+        assert!(part1_safe("^^\n..\n").is_err());
+        assert!(part1_safe(".^\n.x\n").is_err());
+        // Walled in, the guard can only turn around forever
+        assert!(part1_safe(".#.\n#^#\n.#.\n").is_err());
+        assert!(part1_safe(".#..\n.^.#\n#...\n..#.\n").is_err());
+        assert_eq!(part1_safe("^").unwrap(), 1);
+        assert_eq!(part1_safe("#.\n^.\n").unwrap(), 2);
+    }
+}
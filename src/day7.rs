@@ -1,6 +1,15 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use aoc_runner_derive::aoc;
 use rayon::prelude::*;
+use std::fmt::{Display, Write};
+use std::num::ParseIntError;
+use std::ops::ControlFlow;
+use std::str::FromStr;
+
+/// Operators available in part 1, in the order they are tried
+pub const PART1_OPERATORS: &[Operator] = &[Operator::Mul, Operator::Add];
+/// Operators available in part 2, in the order they are tried
+pub const PART2_OPERATORS: &[Operator] = &[Operator::Concat, Operator::Mul, Operator::Add];
 
 #[aoc(day7, part1, AoCS)]
 pub fn part1(input: &str) -> u64 {
@@ -9,43 +18,449 @@ pub fn part1(input: &str) -> u64 {
 
 #[aoc(day7, part1, default)]
 pub fn part1_safe(input: &str) -> Result<u64> {
+    total_calibration::<u64, _>(input, PART1_OPERATORS)
+}
+
+#[aoc(day7, part1, wide)]
+pub fn part1_wide(input: &str) -> Result<u128> {
+    total_calibration::<u128, _>(input, PART1_OPERATORS)
+}
+
+#[aoc(day7, part2, AoCS)]
+pub fn part2(input: &str) -> u64 {
+    part2_safe(input).unwrap()
+}
+
+#[aoc(day7, part2, default)]
+pub fn part2_safe(input: &str) -> Result<u64> {
+    total_calibration::<u64, _>(input, PART2_OPERATORS)
+}
+
+#[aoc(day7, part2, wide)]
+pub fn part2_wide(input: &str) -> Result<u128> {
+    total_calibration::<u128, _>(input, PART2_OPERATORS)
+}
+
+/// Sum of targets of all equations that can be satisfied with operators from `ops`
+pub fn total_calibration<T: Operand, O: Operation<T> + Sync>(input: &str, ops: &[O]) -> Result<T> {
+    input
+        .par_lines()
+        .map(|line| {
+            let equation: Equation<T> = line.parse()?;
+            Ok(if equation.is_solvable(ops) {
+                equation.target
+            } else {
+                T::ZERO
+            })
+        })
+        .try_reduce(|| T::ZERO, checked_sum)
+}
+
+/// Unsigned integer that equation targets and values are parsed into.
+///
+/// All arithmetic is checked, branches that would overflow can never reach the target and are
+/// pruned instead of wrapping around.
+pub trait Operand: Copy + Ord + Send + Sync + FromStr<Err = ParseIntError> {
+    const ZERO: Self;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+
+    /// Exact division, `None` if `rhs` is zero or doesn't divide `self`
+    fn checked_exact_div(self, rhs: Self) -> Option<Self>;
+
+    /// Smallest power of 10 greater than `self`, i.e. the factor `x` is shifted by in `x || self`
+    fn checked_digits_pow(self) -> Option<Self>;
+
+    /// Concatenate decimal representations, e.g. `12.checked_concat(345) == Some(12345)`
+    #[inline(always)]
+    fn checked_concat(self, rhs: Self) -> Option<Self> {
+        self.checked_mul(rhs.checked_digits_pow()?)?
+            .checked_add(rhs)
+    }
+
+    /// Inverse of [`Operand::checked_concat`], strips the digits of `rhs` from the end of `self`
+    #[inline(always)]
+    fn checked_strip_suffix(self, rhs: Self) -> Option<Self> {
+        match rhs.checked_digits_pow() {
+            Some(pow) => self.checked_sub(rhs)?.checked_exact_div(pow),
+            // `rhs` has more digits than any value of `Self`, so only `0 || rhs` is possible
+            None => (self == rhs).then_some(Self::ZERO),
+        }
+    }
+}
+
+macro_rules! impl_operand {
+    ($($t:ty),+) => {$(
+        impl Operand for $t {
+            const ZERO: Self = 0;
+
+            #[inline(always)]
+            fn checked_add(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_add(self, rhs)
+            }
+
+            #[inline(always)]
+            fn checked_sub(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_sub(self, rhs)
+            }
+
+            #[inline(always)]
+            fn checked_mul(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_mul(self, rhs)
+            }
+
+            #[inline(always)]
+            fn checked_exact_div(self, rhs: Self) -> Option<Self> {
+                (rhs != 0 && self % rhs == 0).then(|| self / rhs)
+            }
+
+            #[inline(always)]
+            fn checked_digits_pow(self) -> Option<Self> {
+                (10 as $t).checked_pow(self.checked_ilog10().unwrap_or(0) + 1)
+            }
+        }
+    )+};
+}
+
+impl_operand!(u64, u128);
+
+/// Binary operator that can be placed between two equation values.
+///
+/// Equations are solved backwards, so besides evaluating the operator it has to be able to
+/// recover the left operand from the result and the right operand.
+pub trait Operation<T>: Copy {
+    /// Evaluate `lhs op rhs`, `None` if the result can't be represented
+    fn apply(self, lhs: T, rhs: T) -> Option<T>;
+
+    /// Every `lhs` such that `lhs op rhs == res`
+    fn invert(self, res: T, rhs: T) -> Preimage<T>;
+}
+
+/// Left operands producing a given result, see [`Operation::invert`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preimage<T> {
+    None,
+    One(T),
+    /// Any left operand works, e.g. multiplication by zero
+    Any,
+}
+
+impl<T> From<Option<T>> for Preimage<T> {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(v) => Preimage::One(v),
+            None => Preimage::None,
+        }
+    }
+}
+
+/// Operators from the puzzle, plus subtraction to experiment with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Concat,
+}
+
+impl<T: Operand> Operation<T> for Operator {
+    #[inline(always)]
+    fn apply(self, lhs: T, rhs: T) -> Option<T> {
+        match self {
+            Operator::Add => lhs.checked_add(rhs),
+            Operator::Sub => lhs.checked_sub(rhs),
+            Operator::Mul => lhs.checked_mul(rhs),
+            Operator::Concat => lhs.checked_concat(rhs),
+        }
+    }
+
+    #[inline(always)]
+    fn invert(self, res: T, rhs: T) -> Preimage<T> {
+        match self {
+            Operator::Add => res.checked_sub(rhs).into(),
+            Operator::Sub => res.checked_add(rhs).into(),
+            Operator::Mul if rhs == T::ZERO => {
+                if res == T::ZERO {
+                    Preimage::Any
+                } else {
+                    Preimage::None
+                }
+            }
+            Operator::Mul => res.checked_exact_div(rhs).into(),
+            Operator::Concat => res.checked_strip_suffix(rhs).into(),
+        }
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Operator::Add => "+",
+            Operator::Sub => "-",
+            Operator::Mul => "*",
+            Operator::Concat => "||",
+        })
+    }
+}
+
+/// Single calibration equation, i.e. one line of the puzzle input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Equation<T> {
+    pub target: T,
+    pub values: Vec<T>,
+}
+
+impl<T: Operand> FromStr for Equation<T> {
+    type Err = anyhow::Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let (target, values) = parse_line(line)?;
+        Ok(Equation { target, values })
+    }
+}
+
+impl<T: Operand> Equation<T> {
+    /// Whether any assignment of operators from `ops` satisfies the equation
+    pub fn is_solvable<O: Operation<T>>(&self, ops: &[O]) -> bool {
+        self.search(ops, &mut |_| ControlFlow::Break(())).is_break()
+    }
+
+    /// Number of operator assignments from `ops` that satisfy the equation
+    pub fn count_solutions<O: Operation<T>>(&self, ops: &[O]) -> u64 {
+        let mut count = 0;
+        let _ = self.search(ops, &mut |_| {
+            count += 1;
+            ControlFlow::Continue(())
+        });
+        count
+    }
+
+    /// First operator assignment from `ops` that satisfies the equation, if any
+    pub fn first_solution<O: Operation<T>>(&self, ops: &[O]) -> Option<Solution<'_, T, O>> {
+        let mut solution = None;
+        let _ = self.search(ops, &mut |reversed_ops| {
+            solution = Some(self.solution(reversed_ops));
+            ControlFlow::Break(())
+        });
+        solution
+    }
+
+    /// Every operator assignment from `ops` that satisfies the equation
+    pub fn solutions<O: Operation<T>>(&self, ops: &[O]) -> Vec<Solution<'_, T, O>> {
+        let mut solutions = Vec::new();
+        let _ = self.search(ops, &mut |reversed_ops| {
+            solutions.push(self.solution(reversed_ops));
+            ControlFlow::Continue(())
+        });
+        solutions
+    }
+
+    fn search<O: Operation<T>>(
+        &self,
+        ops: &[O],
+        found: &mut dyn FnMut(&[O]) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        let mut chosen = Vec::with_capacity(self.values.len());
+        search(self.target, &self.values, ops, &mut chosen, found)
+    }
+
+    fn solution<O: Operation<T>>(&self, reversed_ops: &[O]) -> Solution<'_, T, O> {
+        Solution {
+            equation: self,
+            operators: reversed_ops.iter().rev().copied().collect(),
+        }
+    }
+}
+
+/// Operator assignment satisfying an equation, displayed as `190 = 10 * 19`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution<'a, T, O = Operator> {
+    pub equation: &'a Equation<T>,
+    pub operators: Vec<O>,
+}
+
+impl<T: Display, O: Display> Display for Solution<'_, T, O> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Equation { target, values } = self.equation;
+        write!(f, "{target} = {}", values[0])?;
+        for (op, value) in std::iter::zip(&self.operators, &values[1..]) {
+            write!(f, " {op} {value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Depth-first search from the last value backwards.
+///
+/// Operators are evaluated left to right, so the last operator applied is the one next to the
+/// last value. Inverting it narrows the target down for the remaining values, e.g. `+` is undone
+/// by subtraction and `*` only if the target is divisible. Most branches die immediately, so
+/// there is no need to try every operator combination.
+///
+/// Chosen operators are pushed to `chosen` from last to first, `found` receives them in that
+/// order for every assignment that satisfies the equation.
+fn search<T: Operand, O: Operation<T>>(
+    target: T,
+    values: &[T],
+    ops: &[O],
+    chosen: &mut Vec<O>,
+    found: &mut dyn FnMut(&[O]) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    let Some((&last, rest)) = values.split_last() else {
+        return ControlFlow::Continue(());
+    };
+    if rest.is_empty() {
+        return if target == last {
+            found(chosen)
+        } else {
+            ControlFlow::Continue(())
+        };
+    }
+
+    for &op in ops {
+        chosen.push(op);
+        let res = match op.invert(target, last) {
+            Preimage::None => ControlFlow::Continue(()),
+            Preimage::One(target) => search(target, rest, ops, chosen, found),
+            Preimage::Any => evaluate_all(rest[0], &rest[1..], ops, chosen, &mut Vec::new(), found),
+        };
+        chosen.pop();
+        res?;
+    }
+    ControlFlow::Continue(())
+}
+
+/// Report every assignment of `ops` that evaluates `acc` followed by `values` without overflow.
+///
+/// Used where the result of the prefix doesn't matter, `forward` collects operators left to
+/// right and is appended to `chosen` in reverse once a full assignment is found.
+fn evaluate_all<T: Operand, O: Operation<T>>(
+    acc: T,
+    values: &[T],
+    ops: &[O],
+    chosen: &mut Vec<O>,
+    forward: &mut Vec<O>,
+    found: &mut dyn FnMut(&[O]) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    let Some((&first, rest)) = values.split_first() else {
+        let len = chosen.len();
+        chosen.extend(forward.iter().rev());
+        let res = found(chosen);
+        chosen.truncate(len);
+        return res;
+    };
+    for &op in ops {
+        if let Some(acc) = op.apply(acc, first) {
+            forward.push(op);
+            let res = evaluate_all(acc, rest, ops, chosen, forward, found);
+            forward.pop();
+            res?;
+        }
+    }
+    ControlFlow::Continue(())
+}
+
+/// Human readable solution of every equation, one line each
+pub fn explain<O: Operation<u128> + Display>(input: &str, ops: &[O]) -> Result<String> {
+    let mut res = String::new();
+    for line in input.lines() {
+        let equation: Equation<u128> = line.parse()?;
+        if let Some(solution) = equation.first_solution(ops) {
+            writeln!(res, "{solution}")?;
+        } else {
+            writeln!(res, "{line} (no solution)")?;
+        }
+    }
+    Ok(res)
+}
+
+#[aoc(day7, part1, brute)]
+pub fn part1_brute(input: &str) -> Result<u64> {
     let values = input.par_lines().map(|line| {
-        let Some((res, values)) = line.split_once(':') else {
-            anyhow::bail!("Invalid input format")
+        let (expected, values) = parse_line::<u64>(line)?;
+        let Some(combinations) = 1u64.checked_shl(values.len() as u32 - 1) else {
+            anyhow::bail!("too many values to enumerate operators: {line}")
         };
-        let expected: u64 = res.parse()?;
-        let values = values
-            .split_ascii_whitespace()
-            .map(|s| s.parse())
-            .collect::<Result<Vec<u64>, _>>()?;
-        for i in 0..1 << (values.len() - 1) {
+        for i in 0..combinations {
             let op_res =
                 values[1..]
                     .iter()
                     .copied()
                     .enumerate()
-                    .fold(values[0], |acc, (idx, x)| {
+                    .try_fold(values[0], |acc, (idx, x)| {
                         if i & (1 << idx) != 0 {
-                            acc * x
+                            acc.checked_mul(x)
                         } else {
-                            acc + x
+                            acc.checked_add(x)
                         }
                     });
-            if expected == op_res {
+            if op_res == Some(expected) {
+                return Ok(expected);
+            }
+        }
+        Ok(0)
+    });
+    values.try_reduce(|| 0, checked_sum)
+}
+
+#[aoc(day7, part2, brute)]
+pub fn part2_brute(input: &str) -> Result<u64> {
+    let values = input.par_lines().map(|line| {
+        let (expected, values) = parse_line::<u64>(line)?;
+        let Some(combinations) = 3u64.checked_pow(values.len() as u32 - 1) else {
+            anyhow::bail!("too many values to enumerate operators: {line}")
+        };
+        // Every operator position is a base 3 digit: 0 is `+`, 1 is `*` and 2 is `||`
+        for i in 0..combinations {
+            let mut ops = i;
+            let op_res = values[1..].iter().copied().try_fold(values[0], |acc, x| {
+                let op = ops % 3;
+                ops /= 3;
+                match op {
+                    0 => acc.checked_add(x),
+                    1 => acc.checked_mul(x),
+                    _ => acc.checked_concat(x),
+                }
+            });
+            if op_res == Some(expected) {
                 return Ok(expected);
             }
         }
         Ok(0)
     });
-    values.sum()
+    values.try_reduce(|| 0, checked_sum)
+}
+
+fn checked_sum<T: Operand>(l: T, r: T) -> Result<T> {
+    l.checked_add(r)
+        .ok_or_else(|| anyhow!("calibration result overflow"))
+}
+
+fn parse_line<T: Operand>(line: &str) -> Result<(T, Vec<T>)> {
+    let Some((res, values)) = line.split_once(':') else {
+        anyhow::bail!("Invalid input format")
+    };
+    let expected: T = res.parse()?;
+    let values = values
+        .split_ascii_whitespace()
+        .map(|s| s.parse())
+        .collect::<Result<Vec<T>, _>>()?;
+    if values.is_empty() {
+        anyhow::bail!("equation has no values: {line}")
+    }
+    Ok((expected, values))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const DAY7_INPUT: &'static str = include_str!("../input/2024/day7.txt");
-    const DAY7_EXAMPLE: &'static str = "\
+    const DAY7_INPUT: &str = include_str!("../input/2024/day7.txt");
+    const DAY7_EXAMPLE: &str = "\
 190: 10 19
 3267: 81 40 27
 83: 17 5
@@ -59,10 +474,202 @@ mod tests {
     #[test]
     fn part1_example() {
         assert_eq!(part1_safe(DAY7_EXAMPLE).unwrap(), 3749);
+        assert_eq!(part1_brute(DAY7_EXAMPLE).unwrap(), 3749);
     }
 
     #[test]
     fn part1_input() {
         assert_eq!(part1_safe(DAY7_INPUT).unwrap(), 7885693428401);
+        assert_eq!(part1_wide(DAY7_INPUT).unwrap(), 7885693428401);
+        assert_eq!(part1_brute(DAY7_INPUT).unwrap(), 7885693428401);
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_eq!(part2_safe(DAY7_EXAMPLE).unwrap(), 11387);
+        assert_eq!(part2_brute(DAY7_EXAMPLE).unwrap(), 11387);
+    }
+
+    #[test]
+    fn part2_input() {
+        assert_eq!(part2_safe(DAY7_INPUT).unwrap(), 348360680516005);
+        assert_eq!(part2_wide(DAY7_INPUT).unwrap(), 348360680516005);
+    }
+
+    #[test]
+    fn solvable_zeros() {
+        let solvable = |line: &str, ops| line.parse::<Equation<u64>>().unwrap().is_solvable(ops);
+        assert!(solvable("0: 5 0", PART1_OPERATORS));
+        assert!(solvable("5: 5 0", PART1_OPERATORS));
+        assert!(!solvable("4: 5 0", PART1_OPERATORS));
+        assert!(solvable("50: 5 0", PART2_OPERATORS));
+        assert!(solvable("0: 0 0 0", PART2_OPERATORS));
+        // Multiplication by zero still requires the rest of the equation to be representable
+        assert!(solvable("0: 4294967296 4294967296 0", PART1_OPERATORS));
+        assert!(!solvable("0: 18446744073709551615 2 0", &[Operator::Mul]));
+    }
+
+    #[test]
+    fn concat_digits() {
+        assert_eq!(12u64.checked_concat(345), Some(12345));
+        assert_eq!(15u64.checked_concat(6), Some(156));
+        assert_eq!(1u64.checked_concat(10), Some(110));
+        assert_eq!(7u64.checked_concat(0), Some(70));
+        assert_eq!(12345u64.checked_strip_suffix(345), Some(12));
+        assert_eq!(110u64.checked_strip_suffix(10), Some(1));
+        assert_eq!(70u64.checked_strip_suffix(0), Some(7));
+        assert_eq!(345u64.checked_strip_suffix(345), Some(0));
+        assert_eq!(12346u64.checked_strip_suffix(345), None);
+        assert_eq!(u64::MAX.checked_concat(1), None);
+        assert_eq!(u64::MAX.checked_strip_suffix(u64::MAX), Some(0));
+    }
+
+    #[test]
+    fn overflow() {
+        let input = "18446744073709551615: 4294967296 4294967296 1";
+        assert_eq!(part1_safe(input).unwrap(), 0);
+        assert_eq!(part1_brute(input).unwrap(), 0);
+        assert_eq!(part2_safe(input).unwrap(), 0);
+        assert_eq!(part2_brute(input).unwrap(), 0);
+
+        let input = "18446744073709551616: 4294967296 4294967296";
+        assert!(part1_safe(input).is_err());
+        assert_eq!(part1_wide(input).unwrap(), 18446744073709551616);
+        assert_eq!(part2_wide(input).unwrap(), 18446744073709551616);
+
+        let input = "18446744073709551615: 18446744073709551615\n1: 1";
+        assert!(part1_safe(input).is_err());
+        assert_eq!(part1_wide(input).unwrap(), 18446744073709551616);
+    }
+
+    #[test]
+    fn example_solutions() {
+        let equations = DAY7_EXAMPLE
+            .lines()
+            .map(|line| line.parse::<Equation<u64>>().unwrap())
+            .collect::<Vec<_>>();
+
+        let first = |i: usize| {
+            equations[i]
+                .first_solution(PART2_OPERATORS)
+                .map(|s| s.to_string())
+        };
+        assert_eq!(first(0).as_deref(), Some("190 = 10 * 19"));
+        assert_eq!(first(2), None);
+        assert_eq!(first(3).as_deref(), Some("156 = 15 || 6"));
+        assert_eq!(first(4).as_deref(), Some("7290 = 6 * 8 || 6 * 15"));
+        assert_eq!(first(8).as_deref(), Some("292 = 11 + 6 * 16 + 20"));
+
+        let all = equations[1]
+            .solutions(PART1_OPERATORS)
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(all, ["3267 = 81 + 40 * 27", "3267 = 81 * 40 + 27"]);
+        assert!(equations[4].solutions(PART1_OPERATORS).is_empty());
+    }
+
+    #[test]
+    fn zero_solutions() {
+        let equation: Equation<u64> = "0: 1 2 0".parse().unwrap();
+        let all = equation
+            .solutions(PART1_OPERATORS)
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(all, ["0 = 1 * 2 * 0", "0 = 1 + 2 * 0"]);
+        assert_eq!(equation.solutions(PART2_OPERATORS).len(), 3);
+        assert_eq!(equation.count_solutions(PART2_OPERATORS), 3);
+    }
+
+    #[test]
+    fn solutions_match_brute_force() {
+        for line in DAY7_INPUT.lines() {
+            let equation: Equation<u64> = line.parse().unwrap();
+            let solutions = equation.solutions(PART1_OPERATORS);
+            assert_eq!(!solutions.is_empty(), part1_brute(line).unwrap() != 0);
+            assert_eq!(
+                solutions.len() as u64,
+                equation.count_solutions(PART1_OPERATORS)
+            );
+            for solution in solutions {
+                let value = std::iter::zip(&solution.operators, &equation.values[1..])
+                    .try_fold(equation.values[0], |acc, (op, &x)| op.apply(acc, x));
+                assert_eq!(value, Some(equation.target));
+            }
+        }
+    }
+
+    #[test]
+    fn subtraction() {
+        let ops = &[Operator::Add, Operator::Sub, Operator::Mul];
+        let equation: Equation<u64> = "6: 10 2 5 2".parse().unwrap();
+        let all = equation
+            .solutions(ops)
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(all, ["6 = 10 - 2 - 5 * 2"]);
+        assert!(!equation.is_solvable(PART2_OPERATORS));
+
+        // Negative intermediate results can't be represented
+        let equation: Equation<u64> = "3: 1 2 4".parse().unwrap();
+        assert!(!equation.is_solvable(&[Operator::Sub, Operator::Add]));
+    }
+
+    #[test]
+    fn custom_operator() {
+        /// `lhs` to the power of `rhs`
+        #[derive(Debug, Clone, Copy)]
+        struct Pow;
+
+        impl Operation<u64> for Pow {
+            fn apply(self, lhs: u64, rhs: u64) -> Option<u64> {
+                lhs.checked_pow(rhs.try_into().ok()?)
+            }
+
+            fn invert(self, res: u64, rhs: u64) -> Preimage<u64> {
+                if rhs == 0 {
+                    return if res == 1 {
+                        Preimage::Any
+                    } else {
+                        Preimage::None
+                    };
+                }
+                let root = (res as f64).powf(1.0 / rhs as f64).round() as u64;
+                (self.apply(root, rhs) == Some(res)).then_some(root).into()
+            }
+        }
+
+        let equation: Equation<u64> = "1024: 2 5 2".parse().unwrap();
+        assert!(equation.is_solvable(&[Pow]));
+        assert!(!equation.is_solvable(PART2_OPERATORS));
+        let equation: Equation<u64> = "1: 7 3 0".parse().unwrap();
+        assert_eq!(equation.count_solutions(&[Pow]), 1);
+    }
+
+    #[test]
+    fn count_example_solutions() {
+        let counts = DAY7_EXAMPLE
+            .lines()
+            .map(|line| {
+                let equation: Equation<u64> = line.parse().unwrap();
+                equation.count_solutions(PART2_OPERATORS)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(counts, [1, 2, 0, 1, 1, 0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn invalid_equations() {
+        assert!(part1_safe("5:").is_err());
+        assert!(part2_brute("5: ").is_err());
+
+        let long = format!("1:{}", " 1".repeat(65));
+        assert!(part1_brute(&long).is_err());
+        assert_eq!(part1_safe(&long).unwrap(), 1);
+        let long = format!("1:{}", " 1".repeat(42));
+        assert!(part2_brute(&long).is_err());
+        assert_eq!(part2_safe(&long).unwrap(), 1);
     }
 }
@@ -0,0 +1,202 @@
+use crate::grid::{Grid, Pos};
+use anyhow::{bail, Result};
+use aoc_runner_derive::aoc;
+use std::collections::BTreeMap;
+
+#[aoc(day8, part1, AoCS)]
+pub fn part1(input: &str) -> usize {
+    part1_safe(input).unwrap()
+}
+
+#[aoc(day8, part1, default)]
+pub fn part1_safe(input: &str) -> Result<usize> {
+    let map = AntennaMap::parse(input)?;
+    Ok(map.antinodes(Resonance::TwiceAsFar).len())
+}
+
+#[aoc(day8, part2, AoCS)]
+pub fn part2(input: &str) -> usize {
+    part2_safe(input).unwrap()
+}
+
+#[aoc(day8, part2, default)]
+pub fn part2_safe(input: &str) -> Result<usize> {
+    let map = AntennaMap::parse(input)?;
+    Ok(map.antinodes(Resonance::Harmonics).len())
+}
+
+/// Where antinodes of a pair of antennas are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resonance {
+    /// In line with both antennas, twice as far from one as from the other, outside of the pair
+    TwiceAsFar,
+    /// Anywhere exactly in line with both antennas, including on them
+    Harmonics,
+}
+
+/// Antennas on the roof, any letter or digit, with `.` for empty cells
+#[derive(Debug, Clone)]
+pub struct AntennaMap {
+    pub width: usize,
+    pub height: usize,
+    /// Positions of the antennas of each frequency, in row-major order
+    pub frequencies: BTreeMap<u8, Vec<Pos>>,
+}
+
+impl AntennaMap {
+    pub fn parse(input: &str) -> Result<Self> {
+        let grid = Grid::parse(input.as_bytes())?;
+        let mut frequencies = BTreeMap::<u8, Vec<Pos>>::new();
+        for (pos, &c) in grid.iter() {
+            match c {
+                b'.' => {}
+                c if c.is_ascii_alphanumeric() => frequencies.entry(c).or_default().push(pos),
+                _ => bail!(
+                    "row {}, col {}: unexpected {:?}",
+                    pos.row + 1,
+                    pos.col + 1,
+                    c as char
+                ),
+            }
+        }
+        Ok(AntennaMap {
+            width: grid.width(),
+            height: grid.height(),
+            frequencies,
+        })
+    }
+
+    /// Every distinct location of an antinode within the map, in row-major order
+    pub fn antinodes(&self, resonance: Resonance) -> Vec<Pos> {
+        let mut is_antinode = Grid::new(self.width, self.height, false);
+        for antennas in self.frequencies.values() {
+            for (i, &a) in antennas.iter().enumerate() {
+                for &b in &antennas[i + 1..] {
+                    for (from, to) in [(a, b), (b, a)] {
+                        for pos in self.antinodes_from(from, to, resonance) {
+                            is_antinode[pos] = true;
+                        }
+                    }
+                }
+            }
+        }
+        is_antinode
+            .iter()
+            .filter(|(_, &antinode)| antinode)
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+
+    /// Antinodes of the pair walking from `from` towards `to` and beyond, the ones on the other
+    /// side are found with the pair swapped
+    fn antinodes_from(
+        &self,
+        from: Pos,
+        to: Pos,
+        resonance: Resonance,
+    ) -> impl Iterator<Item = Pos> + '_ {
+        let d_row = to.row as isize - from.row as isize;
+        let d_col = to.col as isize - from.col as isize;
+        let (start, step, count) = match resonance {
+            Resonance::TwiceAsFar => (to.offset_by(d_row, d_col), (d_row, d_col), 1),
+            Resonance::Harmonics => {
+                // Cells in between lie exactly in line too when the offset isn't reduced
+                let gcd = gcd(d_row.unsigned_abs(), d_col.unsigned_abs()) as isize;
+                (Some(from), (d_row / gcd, d_col / gcd), usize::MAX)
+            }
+        };
+        std::iter::successors(start, move |pos| pos.offset_by(step.0, step.1))
+            .take_while(|&pos| pos.row < self.height && pos.col < self.width)
+            .take(count)
+    }
+}
+
+fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY8_EXAMPLE: &str = "\
+............
+........0...
+.....0......
+.......0....
+....0.......
+......A.....
+............
+............
+........A...
+.........A..
+............
+............
+";
+
+    #[test]
+    fn part1_example() {
+        assert_eq!(part1(DAY8_EXAMPLE), 14);
+        assert_eq!(part1_safe(DAY8_EXAMPLE).unwrap(), 14);
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_eq!(part2(DAY8_EXAMPLE), 34);
+        assert_eq!(part2_safe(DAY8_EXAMPLE).unwrap(), 34);
+    }
+
+    #[test]
+    fn antinode_positions() {
+        let input = "..........
+..........
+..........
+....a.....
+..........
+.....a....
+..........
+..........
+..........
+..........
+";
+        let map = AntennaMap::parse(input).unwrap();
+        assert_eq!(
+            map.antinodes(Resonance::TwiceAsFar),
+            [Pos::new(1, 3), Pos::new(7, 6)]
+        );
+
+        let input = "T.........
+...T......
+.T........
+..........
+..........
+..........
+..........
+..........
+..........
+..........
+";
+        assert_eq!(part2_safe(input).unwrap(), 9);
+    }
+
+    #[test]
+    fn harmonics_in_between() {
+        // Antennas two cells apart have an antinode right between them
+        let map = AntennaMap::parse("a.a..\n").unwrap();
+        assert_eq!(
+            map.antinodes(Resonance::Harmonics),
+            (0..5).map(|col| Pos::new(0, col)).collect::<Vec<_>>()
+        );
+        assert_eq!(map.antinodes(Resonance::TwiceAsFar), [Pos::new(0, 4)]);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(part1_safe("..#\n").is_err());
+        assert!(part1_safe("..\n.\n").is_err());
+        assert_eq!(part1_safe("a.\n.A\n").unwrap(), 0);
+    }
+}
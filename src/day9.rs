@@ -0,0 +1,209 @@
+use anyhow::{anyhow, ensure, Result};
+use aoc_runner_derive::aoc;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+#[aoc(day9, part1, AoCS)]
+pub fn part1(input: &str) -> u64 {
+    part1_safe(input).unwrap()
+}
+
+#[aoc(day9, part1, default)]
+pub fn part1_safe(input: &str) -> Result<u64> {
+    Ok(DiskMap::parse(input)?.compact_blocks())
+}
+
+#[aoc(day9, part2, AoCS)]
+pub fn part2(input: &str) -> u64 {
+    part2_safe(input).unwrap()
+}
+
+#[aoc(day9, part2, default)]
+pub fn part2_safe(input: &str) -> Result<u64> {
+    Ok(DiskMap::parse(input)?.compact_files())
+}
+
+#[aoc(day9, part2, brute)]
+pub fn part2_brute(input: &str) -> Result<u64> {
+    Ok(DiskMap::parse(input)?.compact_files_brute())
+}
+
+/// Contiguous blocks on the disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub len: usize,
+}
+
+/// Layout of the disk, alternating between files and free space
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskMap {
+    /// Where each file is, indexed by id
+    pub files: Vec<Span>,
+    /// Free space following each file, possibly empty
+    pub free: Vec<Span>,
+}
+
+impl DiskMap {
+    pub fn parse(input: &str) -> Result<Self> {
+        let digits = input.trim_ascii_end().as_bytes();
+        ensure!(!digits.is_empty(), "empty disk map");
+        let mut files = Vec::with_capacity(digits.len() / 2 + 1);
+        let mut free = Vec::with_capacity(digits.len() / 2);
+        let mut start = 0;
+        for (i, &c) in digits.iter().enumerate() {
+            let len = c
+                .is_ascii_digit()
+                .then(|| (c - b'0') as usize)
+                .ok_or_else(|| anyhow!("position {}: unexpected {:?}", i + 1, c as char))?;
+            let spans = if i % 2 == 0 { &mut files } else { &mut free };
+            spans.push(Span { start, len });
+            start += len;
+        }
+        Ok(DiskMap { files, free })
+    }
+
+    /// Checksum after moving blocks one at a time from the end to the leftmost free block
+    pub fn compact_blocks(&self) -> u64 {
+        let total = self.files.last().map_or(0, |file| file.start + file.len);
+        let mut blocks = vec![None; total];
+        for (id, file) in self.files.iter().enumerate() {
+            blocks[file.start..file.start + file.len].fill(Some(id));
+        }
+
+        let (mut free, mut used) = (0, total);
+        loop {
+            while free < used && blocks[free].is_some() {
+                free += 1;
+            }
+            while used > free && blocks[used - 1].is_none() {
+                used -= 1;
+            }
+            if free + 1 >= used {
+                break;
+            }
+            blocks.swap(free, used - 1);
+        }
+
+        blocks
+            .iter()
+            .enumerate()
+            .map_while(|(pos, id)| Some((pos * (*id)?) as u64))
+            .sum()
+    }
+
+    /// Checksum after moving whole files, from the highest id down, to the leftmost free span
+    /// large enough to hold them
+    pub fn compact_files(&self) -> u64 {
+        // Starts of free spans by length, so that the leftmost one of each length is at hand
+        let mut free_by_len: [BinaryHeap<Reverse<usize>>; 10] = Default::default();
+        for span in self.free.iter().filter(|span| span.len > 0) {
+            free_by_len[span.len].push(Reverse(span.start));
+        }
+
+        let mut res = 0;
+        for (id, file) in self.files.iter().enumerate().rev() {
+            let leftmost = (file.len.max(1)..free_by_len.len())
+                .filter_map(|len| Some((free_by_len[len].peek()?.0, len)))
+                .min()
+                .filter(|&(start, _)| start < file.start);
+            let start = match leftmost {
+                Some((start, len)) => {
+                    free_by_len[len].pop();
+                    // Space freed behind the file is right of every file left to move
+                    if len > file.len {
+                        free_by_len[len - file.len].push(Reverse(start + file.len));
+                    }
+                    start
+                }
+                None => file.start,
+            };
+            res += checksum(id, Span { start, ..*file });
+        }
+        res
+    }
+
+    /// Same as [`DiskMap::compact_files`], looking for free space one span at a time
+    pub fn compact_files_brute(&self) -> u64 {
+        let mut free = self.free.clone();
+        let mut res = 0;
+        for (id, file) in self.files.iter().enumerate().rev() {
+            let mut start = file.start;
+            if let Some(span) = free
+                .iter_mut()
+                .take_while(|span| span.start < file.start)
+                .find(|span| span.len >= file.len)
+            {
+                start = span.start;
+                span.start += file.len;
+                span.len -= file.len;
+            }
+            res += checksum(id, Span { start, ..*file });
+        }
+        res
+    }
+}
+
+/// Sum of each block's position multiplied by `id`, for a file at `span`
+fn checksum(id: usize, span: Span) -> u64 {
+    let Span { start, len } = span;
+    // start + (start + 1) + ... + (start + len - 1)
+    (id * (len * start + len * len.saturating_sub(1) / 2)) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Random disk map from `input/generated`, not a puzzle input, with no known answers
+    const DAY9_GENERATED: &str = include_str!("../input/generated/day9.txt");
+    const DAY9_EXAMPLE: &str = "2333133121414131402\n";
+
+    #[test]
+    fn part1_example() {
+        assert_eq!(part1(DAY9_EXAMPLE), 1928);
+        assert_eq!(part1_safe(DAY9_EXAMPLE).unwrap(), 1928);
+        assert_eq!(part1("12345"), 60);
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_eq!(part2(DAY9_EXAMPLE), 2858);
+        assert_eq!(part2_safe(DAY9_EXAMPLE).unwrap(), 2858);
+        assert_eq!(part2_brute(DAY9_EXAMPLE).unwrap(), 2858);
+        assert_eq!(part2("12345"), 132);
+    }
+
+    #[test]
+    fn part2_generated() {
+        assert_eq!(
+            part2_safe(DAY9_GENERATED).unwrap(),
+            part2_brute(DAY9_GENERATED).unwrap()
+        );
+    }
+
+    #[test]
+    fn free_span_index_matches_brute() {
+        let prefixes = [1, 2, 3, 10, 101, 1001].map(|len| &DAY9_GENERATED[..len]);
+        // Empty files never move
+        for input in prefixes.into_iter().chain(["1020304", "90909", "0"]) {
+            assert_eq!(
+                part2_safe(input).unwrap(),
+                part2_brute(input).unwrap(),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let disk = DiskMap::parse("123\n").unwrap();
+        assert_eq!(
+            disk.files,
+            [Span { start: 0, len: 1 }, Span { start: 3, len: 3 }]
+        );
+        assert_eq!(disk.free, [Span { start: 1, len: 2 }]);
+        assert!(DiskMap::parse("").is_err());
+        assert!(DiskMap::parse("12a4").is_err());
+    }
+}
@@ -0,0 +1,657 @@
+use anyhow::{anyhow, ensure, Result};
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut};
+
+/// Cell coordinates, rows grow downwards and columns to the right
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Pos {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Pos {
+    pub const fn new(row: usize, col: usize) -> Self {
+        Pos { row, col }
+    }
+
+    /// Neighbouring position towards `dir`, `None` if a coordinate would become negative
+    #[inline(always)]
+    pub fn step(self, dir: Direction) -> Option<Pos> {
+        self.step_by(dir, 1)
+    }
+
+    /// Position `n` steps towards `dir`, `None` if a coordinate would become negative
+    #[inline(always)]
+    pub fn step_by(self, dir: Direction, n: usize) -> Option<Pos> {
+        let (d_row, d_col) = dir.offset();
+        let n = isize::try_from(n).ok()?;
+        self.offset_by(d_row.checked_mul(n)?, d_col.checked_mul(n)?)
+    }
+
+    /// Position moved by `d_row` rows and `d_col` columns, `None` if a coordinate would become
+    /// negative
+    #[inline(always)]
+    pub fn offset_by(self, d_row: isize, d_col: isize) -> Option<Pos> {
+        Some(Pos {
+            row: self.row.checked_add_signed(d_row)?,
+            col: self.col.checked_add_signed(d_col)?,
+        })
+    }
+}
+
+/// One of the eight directions to a neighbouring cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Direction {
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl Direction {
+    /// All directions, clockwise from [`Direction::Up`]
+    pub const ALL: [Direction; 8] = [
+        Direction::Up,
+        Direction::UpRight,
+        Direction::Right,
+        Direction::DownRight,
+        Direction::Down,
+        Direction::DownLeft,
+        Direction::Left,
+        Direction::UpLeft,
+    ];
+
+    /// Directions to cells sharing an edge, clockwise from [`Direction::Up`]
+    pub const ORTHOGONAL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    /// Directions to cells sharing only a corner, clockwise from [`Direction::UpRight`]
+    pub const DIAGONAL: [Direction; 4] = [
+        Direction::UpRight,
+        Direction::DownRight,
+        Direction::DownLeft,
+        Direction::UpLeft,
+    ];
+
+    /// Row and column offsets of a single step
+    #[inline(always)]
+    pub const fn offset(self) -> (isize, isize) {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::UpRight => (-1, 1),
+            Direction::Right => (0, 1),
+            Direction::DownRight => (1, 1),
+            Direction::Down => (1, 0),
+            Direction::DownLeft => (1, -1),
+            Direction::Left => (0, -1),
+            Direction::UpLeft => (-1, -1),
+        }
+    }
+
+    /// Direction after turning clockwise by `eighths` of a full turn
+    #[inline(always)]
+    pub fn rotate(self, eighths: usize) -> Self {
+        Self::ALL[(self as usize + eighths) % 8]
+    }
+
+    /// Direction after turning 90 degrees clockwise
+    #[inline(always)]
+    pub fn turn_right(self) -> Self {
+        self.rotate(2)
+    }
+
+    /// Direction after turning 90 degrees counterclockwise
+    #[inline(always)]
+    pub fn turn_left(self) -> Self {
+        self.rotate(6)
+    }
+
+    #[inline(always)]
+    pub fn opposite(self) -> Self {
+        self.rotate(4)
+    }
+}
+
+/// Rectangular grid of cells stored row by row.
+///
+/// Rows may be followed by padding, which lets a grid parsed from text keep its line breaks in
+/// place and borrow the text instead of copying it. Padding is never reachable through [`Pos`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T, C = Vec<T>> {
+    cells: C,
+    width: usize,
+    height: usize,
+    /// Distance between the starts of consecutive rows
+    stride: usize,
+    cell: PhantomData<T>,
+}
+
+impl<'a> Grid<u8, &'a [u8]> {
+    /// Grid with one row per line of `input`, a trailing line break is optional.
+    ///
+    /// Lines may end with either `\n` or `\r\n`, as long as it is the same for every line.
+    pub fn parse(input: &'a [u8]) -> Result<Self> {
+        let (width, newline) = Self::first_line(input);
+        ensure!(width > 0, "grid has no columns");
+
+        let body = input.strip_suffix(newline).unwrap_or(input);
+        let mut rows = body.split(|&c| c == b'\n').peekable();
+        let mut height = 0;
+        while let Some(row) = rows.next() {
+            height += 1;
+            // Every row but the last one is followed by the full line break
+            let cells = match newline.strip_suffix(b"\n") {
+                Some(cr) if rows.peek().is_some() => row
+                    .strip_suffix(cr)
+                    .ok_or_else(|| anyhow!("row {height}: inconsistent line break"))?,
+                _ => row,
+            };
+            ensure!(
+                cells.len() == width,
+                "row {height}: expected {width} cells, found {}",
+                cells.len()
+            );
+        }
+
+        Ok(Grid {
+            cells: body,
+            width,
+            height,
+            stride: width + newline.len(),
+            cell: PhantomData,
+        })
+    }
+
+    /// Same as [`Grid::parse`], trusting every line to be as long as the first one
+    pub fn parse_unchecked(input: &'a [u8]) -> Self {
+        let (width, newline) = Self::first_line(input);
+        let body = input.strip_suffix(newline).unwrap_or(input);
+        let stride = width + newline.len();
+        Grid {
+            cells: body,
+            width,
+            height: (body.len() + newline.len()) / stride.max(1),
+            stride,
+            cell: PhantomData,
+        }
+    }
+
+    /// Width of the first line of `input` and the line break ending it
+    fn first_line(input: &[u8]) -> (usize, &'static [u8]) {
+        let first_len = input
+            .iter()
+            .position(|&c| c == b'\n')
+            .unwrap_or(input.len());
+        let newline: &'static [u8] = if input[..first_len].ends_with(b"\r") {
+            b"\r\n"
+        } else {
+            b"\n"
+        };
+        (first_len + 1 - newline.len(), newline)
+    }
+}
+
+impl<T> Grid<T> {
+    /// Grid with each cell initialized to `f(pos)`
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(Pos) -> T) -> Self {
+        let cells = (0..height)
+            .flat_map(|row| (0..width).map(move |col| Pos { row, col }))
+            .map(&mut f)
+            .collect();
+        Grid {
+            cells,
+            width,
+            height,
+            stride: width,
+            cell: PhantomData,
+        }
+    }
+
+    /// Grid with every cell set to `value`
+    pub fn new(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Grid::from_fn(width, height, |_| value.clone())
+    }
+}
+
+impl<T, C: AsRef<[T]>> Grid<T, C> {
+    /// Grid of the same size with each cell set to `f(pos, cell)`
+    pub fn map<U>(&self, mut f: impl FnMut(Pos, &T) -> U) -> Grid<U> {
+        Grid::from_fn(self.width, self.height, |pos| f(pos, &self[pos]))
+    }
+
+    #[inline(always)]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline(always)]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Distance between the starts of consecutive rows in [`Grid::as_slice`]
+    #[inline(always)]
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Cells in row-major order, with each row but the last followed by padding up to the stride
+    #[inline(always)]
+    pub fn as_slice(&self) -> &[T] {
+        self.cells.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn contains(&self, pos: Pos) -> bool {
+        pos.row < self.height && pos.col < self.width
+    }
+
+    #[inline(always)]
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        self.contains(pos)
+            .then(|| &self.as_slice()[pos.row * self.stride + pos.col])
+    }
+
+    /// Cells of row `row`, panics if it is out of bounds
+    #[inline(always)]
+    pub fn row(&self, row: usize) -> &[T] {
+        assert!(row < self.height, "row {row} out of bounds");
+        let start = row * self.stride;
+        &self.as_slice()[start..start + self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).map(|row| self.row(row))
+    }
+
+    /// All positions in row-major order
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let width = self.width;
+        (0..self.height).flat_map(move |row| (0..width).map(move |col| Pos { row, col }))
+    }
+
+    /// All cells with their position in row-major order
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().map(|pos| (pos, &self[pos]))
+    }
+
+    /// Neighbours of `pos` sharing an edge with it, clockwise from the one above
+    pub fn neighbours4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.neighbours(pos, Direction::ORTHOGONAL)
+    }
+
+    /// Neighbours of `pos` sharing an edge or a corner with it, clockwise from the one above
+    pub fn neighbours8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.neighbours(pos, Direction::ALL)
+    }
+
+    fn neighbours<const N: usize>(
+        &self,
+        pos: Pos,
+        dirs: [Direction; N],
+    ) -> impl Iterator<Item = Pos> + '_ {
+        dirs.into_iter()
+            .filter_map(move |dir| pos.step(dir))
+            .filter(|&pos| self.contains(pos))
+    }
+
+    /// Positions from `start` included towards `dir`, until the edge of the grid
+    pub fn ray(&self, start: Pos, dir: Direction) -> impl Iterator<Item = Pos> + '_ {
+        std::iter::successors(Some(start).filter(|&pos| self.contains(pos)), move |pos| {
+            pos.step(dir).filter(|&pos| self.contains(pos))
+        })
+    }
+}
+
+impl<T, C: AsRef<[T]>> Grid<T, C> {
+    /// Grid with its own copy of the cells, padding included
+    pub fn owned(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid {
+            cells: self.as_slice().to_vec(),
+            width: self.width,
+            height: self.height,
+            stride: self.stride,
+            cell: PhantomData,
+        }
+    }
+}
+
+impl<T, C: AsRef<[T]> + AsMut<[T]>> Grid<T, C> {
+    #[inline(always)]
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        self.contains(pos)
+            .then(|| &mut self.cells.as_mut()[pos.row * self.stride + pos.col])
+    }
+}
+
+impl<T, C: AsRef<[T]>> Index<Pos> for Grid<T, C> {
+    type Output = T;
+
+    #[inline(always)]
+    fn index(&self, pos: Pos) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("{pos:?} out of bounds"))
+    }
+}
+
+impl<T, C: AsRef<[T]> + AsMut<[T]>> IndexMut<Pos> for Grid<T, C> {
+    #[inline(always)]
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("{pos:?} out of bounds"))
+    }
+}
+
+/// How [`Grid::render`] sets marked cells apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    /// Marked cells are coloured with ANSI escape codes
+    Ansi,
+    /// Unmarked cells are replaced with `.`
+    Dots,
+}
+
+impl<C: AsRef<[u8]>> Grid<u8, C> {
+    /// Grid as text with one line per row, cells marked in `marked` are set apart
+    pub fn render(&self, marked: &Grid<bool>, highlight: Highlight) -> String {
+        let mut res = String::with_capacity(self.height * (self.width + 1));
+        for (row, cells) in self.rows().enumerate() {
+            for (col, &c) in cells.iter().enumerate() {
+                let is_marked = marked.get(Pos::new(row, col)) == Some(&true);
+                match (highlight, is_marked) {
+                    (Highlight::Ansi, true) => {
+                        res.push_str("\x1b[1;33m");
+                        res.push(c as char);
+                        res.push_str("\x1b[0m");
+                    }
+                    (Highlight::Dots, false) => res.push('.'),
+                    _ => res.push(c as char),
+                }
+            }
+            res.push('\n');
+        }
+        res
+    }
+}
+
+impl<T: Clone, C: AsRef<[T]>> Grid<T, C> {
+    /// Grid turned 90 degrees clockwise
+    pub fn rotated_right(&self) -> Grid<T> {
+        Grid::from_fn(self.height, self.width, |pos| {
+            self[Pos::new(self.height - 1 - pos.col, pos.row)].clone()
+        })
+    }
+
+    /// Grid mirrored left to right
+    pub fn flipped(&self) -> Grid<T> {
+        Grid::from_fn(self.width, self.height, |pos| {
+            self[Pos::new(pos.row, self.width - 1 - pos.col)].clone()
+        })
+    }
+}
+
+/// Orientations in which a [`Pattern`] is looked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    /// Only as given
+    Fixed,
+    /// Turned by any multiple of 90 degrees
+    Rotations,
+    /// Turned by any multiple of 90 degrees, and mirrored
+    RotationsAndReflections,
+}
+
+/// Small template looked for in a grid, `None` cells match anything
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern<T> {
+    /// Distinct orientations of the template
+    variants: Vec<Grid<Option<T>>>,
+    /// Cells of each variant that aren't wildcards, relative to its top left corner
+    fixed: Vec<Vec<(Pos, T)>>,
+}
+
+/// Placement of a [`Pattern`] in a grid, `pos` is the top left corner of its variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PatternMatch {
+    pub pos: Pos,
+    pub variant: usize,
+}
+
+impl Pattern<u8> {
+    /// Template with one row per line of `input`, where `wildcard` matches anything
+    pub fn parse(input: &[u8], wildcard: u8, symmetry: Symmetry) -> Result<Self> {
+        let template = Grid::parse(input)?;
+        Ok(Pattern::new(
+            template.map(|_, &c| (c != wildcard).then_some(c)),
+            symmetry,
+        ))
+    }
+}
+
+impl<T: Clone + PartialEq> Pattern<T> {
+    pub fn new(template: Grid<Option<T>>, symmetry: Symmetry) -> Self {
+        let mut orientations = vec![template];
+        if symmetry == Symmetry::RotationsAndReflections {
+            orientations.push(orientations[0].flipped());
+        }
+        if symmetry != Symmetry::Fixed {
+            for i in 0..orientations.len() {
+                let mut turned = orientations[i].clone();
+                for _ in 0..3 {
+                    turned = turned.rotated_right();
+                    orientations.push(turned.clone());
+                }
+            }
+        }
+
+        let mut variants = Vec::new();
+        for orientation in orientations {
+            if !variants.contains(&orientation) {
+                variants.push(orientation);
+            }
+        }
+        let fixed = variants
+            .iter()
+            .map(|variant| {
+                variant
+                    .iter()
+                    .filter_map(|(offset, cell)| Some((offset, cell.clone()?)))
+                    .collect()
+            })
+            .collect();
+        Pattern { variants, fixed }
+    }
+
+    /// Distinct orientations looked for, indexed by [`PatternMatch::variant`]
+    pub fn variants(&self) -> &[Grid<Option<T>>] {
+        &self.variants
+    }
+
+    /// Whether variant `variant` matches with its top left corner at `pos`
+    pub fn matches_at(&self, grid: &Grid<T, impl AsRef<[T]>>, pos: Pos, variant: usize) -> bool {
+        self.fixed[variant].iter().all(|(offset, expected)| {
+            grid.get(Pos::new(pos.row + offset.row, pos.col + offset.col)) == Some(expected)
+        })
+    }
+
+    /// Positions of the cells that aren't wildcards in placement `m`
+    pub fn cells(&self, m: PatternMatch) -> impl Iterator<Item = Pos> + '_ {
+        self.fixed[m.variant]
+            .iter()
+            .map(move |(offset, _)| Pos::new(m.pos.row + offset.row, m.pos.col + offset.col))
+    }
+
+    /// Every placement of any variant in `grid`, by position in row-major order then variant
+    pub fn find<'a, C: AsRef<[T]>>(
+        &'a self,
+        grid: &'a Grid<T, C>,
+    ) -> impl Iterator<Item = PatternMatch> + 'a {
+        grid.positions()
+            .flat_map(|pos| {
+                (0..self.variants.len()).map(move |variant| PatternMatch { pos, variant })
+            })
+            .filter(|m| self.matches_at(grid, m.pos, m.variant))
+    }
+
+    /// Number of placements of any variant in `grid`, see [`Pattern::find`]
+    pub fn count(&self, grid: &Grid<T, impl AsRef<[T]>>) -> usize {
+        self.find(grid).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        for input in ["abc\ndef\n", "abc\ndef", "abc\r\ndef\r\n", "abc\r\ndef"] {
+            let grid = Grid::parse(input.as_bytes()).unwrap();
+            assert_eq!((grid.width(), grid.height()), (3, 2), "{input:?}");
+            assert_eq!(grid.rows().collect::<Vec<_>>(), [b"abc", b"def"]);
+            assert_eq!(grid[Pos::new(1, 2)], b'f');
+            assert_eq!(grid.get(Pos::new(0, 3)), None);
+            assert_eq!(grid.get(Pos::new(2, 0)), None);
+            assert_eq!(grid.as_slice()[grid.stride()], b'd');
+            assert_eq!(grid.as_slice().as_ptr(), input.as_ptr());
+            assert_eq!(Grid::parse_unchecked(input.as_bytes()), grid);
+            assert_eq!(grid.owned().as_slice(), grid.as_slice());
+        }
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for input in [
+            "",
+            "\n",
+            "abc\nde\n",
+            "abc\ndefg\n",
+            "abc\n\n",
+            "abc\r\ndef\nghi\r\n",
+        ] {
+            assert!(Grid::parse(input.as_bytes()).is_err(), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn neighbours() {
+        let grid = Grid::new(3, 2, ());
+        assert_eq!(
+            grid.neighbours4(Pos::new(0, 0)).collect::<Vec<_>>(),
+            [Pos::new(0, 1), Pos::new(1, 0)]
+        );
+        assert_eq!(grid.neighbours8(Pos::new(1, 1)).count(), 5);
+        assert_eq!(grid.neighbours8(Pos::new(0, 2)).count(), 3);
+    }
+
+    #[test]
+    fn ray() {
+        let grid = Grid::from_fn(4, 3, |pos| pos.row * 4 + pos.col);
+        let cells = |start, dir| {
+            grid.ray(start, dir)
+                .map(|pos| grid[pos])
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(cells(Pos::new(0, 1), Direction::Right), [1, 2, 3]);
+        assert_eq!(cells(Pos::new(0, 1), Direction::DownRight), [1, 6, 11]);
+        assert_eq!(cells(Pos::new(2, 3), Direction::UpLeft), [11, 6, 1]);
+        assert_eq!(cells(Pos::new(1, 0), Direction::Left), [4]);
+        assert_eq!(cells(Pos::new(3, 0), Direction::Up), []);
+    }
+
+    #[test]
+    fn directions() {
+        for dir in Direction::ALL {
+            assert_eq!(dir.turn_right().turn_left(), dir);
+            assert_eq!(dir.opposite().opposite(), dir);
+            let (d_row, d_col) = dir.offset();
+            assert_eq!(dir.opposite().offset(), (-d_row, -d_col));
+        }
+        assert_eq!(Direction::Up.turn_right(), Direction::Right);
+        assert_eq!(
+            Pos::new(2, 2).step_by(Direction::UpLeft, 2),
+            Some(Pos::new(0, 0))
+        );
+        assert_eq!(Pos::new(2, 2).step_by(Direction::UpLeft, 3), None);
+    }
+
+    #[test]
+    fn transforms() {
+        let grid = Grid::parse(b"abc\ndef\n").unwrap().owned();
+        let rows = |grid: &Grid<u8>| grid.rows().map(|row| row.to_vec()).collect::<Vec<_>>();
+        assert_eq!(rows(&grid.rotated_right()), [b"da", b"eb", b"fc"]);
+        assert_eq!(rows(&grid.flipped()), [b"cba", b"fed"]);
+        let turned = (0..4).fold(grid.clone(), |grid, _| grid.rotated_right());
+        assert_eq!(rows(&turned), rows(&grid));
+    }
+
+    #[test]
+    fn pattern_variants() {
+        let count = |template: &[u8], symmetry| {
+            Pattern::parse(template, b'.', symmetry)
+                .unwrap()
+                .variants()
+                .len()
+        };
+        assert_eq!(count(b"ab\ncd", Symmetry::Fixed), 1);
+        assert_eq!(count(b"ab\ncd", Symmetry::Rotations), 4);
+        assert_eq!(count(b"ab\ncd", Symmetry::RotationsAndReflections), 8);
+        assert_eq!(count(b"ab\nba", Symmetry::RotationsAndReflections), 2);
+        assert_eq!(count(b"a.a", Symmetry::RotationsAndReflections), 2);
+        assert_eq!(count(b"a", Symmetry::RotationsAndReflections), 1);
+    }
+
+    #[test]
+    fn pattern_find() {
+        let grid = Grid::parse(b"axa\nxbx\naxb\n").unwrap();
+        let pattern = Pattern::parse(b"a.\n.b", b'.', Symmetry::Fixed).unwrap();
+        assert_eq!(
+            pattern.find(&grid).map(|m| m.pos).collect::<Vec<_>>(),
+            [Pos::new(0, 0)]
+        );
+
+        let grid = Grid::parse(b"abab\nbxba\nabab\n").unwrap();
+        let pattern = Pattern::parse(b"ab", b'.', Symmetry::Rotations).unwrap();
+        // Left to right, top to bottom, right to left and bottom to top
+        assert_eq!(pattern.count(&grid), 4 + 3 + 3 + 3);
+        let matches = pattern.find(&grid).take(2).collect::<Vec<_>>();
+        assert_eq!(
+            matches,
+            [
+                PatternMatch {
+                    pos: Pos::new(0, 0),
+                    variant: 0
+                },
+                PatternMatch {
+                    pos: Pos::new(0, 0),
+                    variant: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn render() {
+        let grid = Grid::parse(b"ab\ncd\n").unwrap();
+        let marked = Grid::from_fn(2, 2, |pos| pos.row == pos.col);
+        assert_eq!(grid.render(&marked, Highlight::Dots), "a.\n.d\n");
+        assert_eq!(
+            grid.render(&marked, Highlight::Ansi),
+            "\x1b[1;33ma\x1b[0mb\nc\x1b[1;33md\x1b[0m\n"
+        );
+    }
+}
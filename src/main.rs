@@ -1,4 +1,5 @@
 use anyhow::{anyhow, Result};
+use aoc2024::grid::Highlight;
 use clap::Parser;
 use std::io::{BufRead, Read};
 
@@ -6,17 +7,36 @@ fn dispatch(day: u8, part: u8, input: &str) -> Result<String> {
     match (day, part) {
         (1, 1) => aoc2024::day1::part1_safe(input).map(|res| res.to_string()),
         (1, 2) => aoc2024::day1::part2_safe(input).map(|res| res.to_string()),
-        (2, 1) => Ok(aoc2024::day2::part1(input)).map(|res| res.to_string()),
-        (2, 2) => Ok(aoc2024::day2::part2(input)).map(|res| res.to_string()),
+        (2, 1) => aoc2024::day2::part1_safe(input).map(|res| res.to_string()),
+        (2, 2) => aoc2024::day2::part2_safe(input).map(|res| res.to_string()),
         (3, 1) => aoc2024::day3::part1_safe(input).map(|res| res.to_string()),
         (3, 2) => aoc2024::day3::part2_safe(input).map(|res| res.to_string()),
-        (4, 1) => Ok(aoc2024::day4::part1(input)).map(|res| res.to_string()),
-        (4, 2) => Ok(aoc2024::day4::part2(input)).map(|res| res.to_string()),
+        (4, 1) => aoc2024::day4::part1_safe(input).map(|res| res.to_string()),
+        (4, 2) => aoc2024::day4::part2_safe(input).map(|res| res.to_string()),
+        (6, 1) => aoc2024::day6::part1_safe(input).map(|res| res.to_string()),
+        (6, 2) => aoc2024::day6::part2_safe(input).map(|res| res.to_string()),
         (7, 1) => aoc2024::day7::part1_safe(input).map(|res| res.to_string()),
+        (7, 2) => aoc2024::day7::part2_safe(input).map(|res| res.to_string()),
+        (8, 1) => aoc2024::day8::part1_safe(input).map(|res| res.to_string()),
+        (8, 2) => aoc2024::day8::part2_safe(input).map(|res| res.to_string()),
+        (9, 1) => aoc2024::day9::part1_safe(input).map(|res| res.to_string()),
+        (9, 2) => aoc2024::day9::part2_safe(input).map(|res| res.to_string()),
         _ => Err(anyhow!("no solution for day {day} part {part}")),
     }
 }
 
+fn explain(day: u8, part: u8, input: &str, highlight: Highlight) -> Result<String> {
+    match (day, part) {
+        (2, 1) => aoc2024::day2::explain(input, &aoc2024::day2::SafetyRules::PART1),
+        (2, 2) => aoc2024::day2::explain(input, &aoc2024::day2::SafetyRules::PART2),
+        (4, 1) => aoc2024::day4::explain_xmas(input, highlight),
+        (4, 2) => aoc2024::day4::explain_x_mas(input, highlight),
+        (7, 1) => aoc2024::day7::explain(input, aoc2024::day7::PART1_OPERATORS),
+        (7, 2) => aoc2024::day7::explain(input, aoc2024::day7::PART2_OPERATORS),
+        _ => Err(anyhow!("no explanation for day {day} part {part}")),
+    }
+}
+
 fn run() -> Result<()> {
     let cli = Cli::parse();
 
@@ -86,6 +106,16 @@ fn run() -> Result<()> {
         (day, part, input)
     };
 
+    if cli.explain {
+        let highlight = if cli.color {
+            Highlight::Ansi
+        } else {
+            Highlight::Dots
+        };
+        print!("{}", explain(day, part, &input, highlight)?);
+        return Ok(());
+    }
+
     let iterations = cli.iterations.unwrap_or(1);
 
     let start = cli.time.then(std::time::Instant::now);
@@ -135,6 +165,14 @@ struct Cli {
     #[clap(long, short)]
     time: bool,
 
+    /// Print how the solution was found instead of the answer
+    #[clap(long, short)]
+    explain: bool,
+
+    /// Highlight grid cells with colours instead of hiding the others when explaining
+    #[clap(long, requires = "explain")]
+    color: bool,
+
     /// Measure solution execution time
     #[clap(long, short, value_parser = clap::value_parser!(u32).range(1..))]
     iterations: Option<u32>,